@@ -0,0 +1,311 @@
+//! Derive macros for [mafic](../mafic/index.html).
+//!
+//! These are re-exported by `mafic` and should be used from there.
+
+use proc_macro::{ Delimiter, Group, Ident, Literal, Punct, Spacing, Span };
+use proc_macro::{ TokenStream, TokenTree };
+
+/// Derive `mafic::bundle::Bundle` on a struct whose fields are all bundles.
+///
+/// Fields marked with `#[bundle(flip)]` are connected in the opposite
+/// direction.
+#[proc_macro_derive(Bundle, attributes(bundle))]
+pub fn derive_bundle(input: TokenStream) -> TokenStream {
+    let res = Struct::parse(input).and_then(|s| {
+        s.impl_bundle().parse::<TokenStream>()
+            .map_err(|e| format!("failed to derive Bundle: {}", e))
+    });
+    res.unwrap_or_else(|msg| compile_error(&msg))
+}
+
+/// Return a `compile_error!` invocation with the given message.
+fn compile_error(msg: &str) -> TokenStream {
+    let span = Span::call_site();
+    let msg = TokenTree::Literal(Literal::string(msg));
+    [
+        TokenTree::Ident(Ident::new("compile_error", span)),
+        TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, msg.into())),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ].into_iter().collect()
+}
+
+/// Nest a list of items in pairs (ie. `f(a, f(b, c))`), so that any 
+/// number of items can be joined. 
+fn nest(items: &[String], f: &str, empty: &str) -> String {
+    match items {
+        [] => empty.to_string(),
+        [item] => item.clone(),
+        [item, rest @ ..] => format!("{}({}, {})", f, item, nest(rest, f, empty)),
+    }
+}
+
+/// A field in a struct.
+struct Field {
+    name: String,
+    ty: String,
+    flip: bool,
+}
+
+/// A struct with named fields.
+struct Struct {
+    name: String,
+    /// Generic parameters (without defaults)
+    params: Vec<String>,
+    /// Generic arguments (ie. the names of the generic parameters)
+    args: Vec<String>,
+    /// Set when some generic parameter is a type
+    has_type_params: bool,
+    /// Predicates in the where clause
+    predicates: String,
+    fields: Vec<Field>,
+}
+impl Struct {
+    fn parse(input: TokenStream) -> Result<Self, String> {
+        let tokens: Vec<TokenTree> = input.into_iter().collect();
+        let mut i = skip_attrs(&tokens, 0, &mut false);
+        i = skip_vis(&tokens, i);
+        if !is_ident(tokens.get(i), "struct") {
+            return Err("Bundle can only be derived on structs".to_string());
+        }
+        let name = match tokens.get(i + 1) {
+            Some(TokenTree::Ident(id)) => id.to_string(),
+            _ => return Err("expected struct name".to_string()),
+        };
+        i += 2;
+
+        // Generic parameters
+        let mut params = Vec::new();
+        let mut args = Vec::new();
+        let mut has_type_params = false;
+        if is_punct(tokens.get(i), '<') {
+            let end = matching_angle(&tokens, i)?;
+            for param in split_commas(&tokens[i + 1..end]) {
+                let (arg, is_type) = param_name(param)?;
+                has_type_params |= is_type;
+                args.push(arg);
+                let default = param.iter().position(|t| is_punct(Some(t), '='));
+                params.push(to_string(&param[..default.unwrap_or(param.len())]));
+            }
+            i = end + 1;
+        }
+
+        // Where clause, followed by the fields
+        let mut predicates = String::new();
+        if is_ident(tokens.get(i), "where") {
+            let end = tokens[i..].iter()
+                .position(|t| is_group(t, Delimiter::Brace))
+                .map(|pos| i + pos)
+                .unwrap_or(tokens.len());
+            predicates = to_string(&tokens[i + 1..end]);
+            i = end;
+        }
+        let body = match tokens.get(i) {
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => {
+                g.stream()
+            },
+            _ => return Err(
+                "Bundle can only be derived on structs with named fields"
+                .to_string()
+            ),
+        };
+
+        let body: Vec<TokenTree> = body.into_iter().collect();
+        let mut fields = Vec::new();
+        for field in split_commas(&body) {
+            let mut flip = false;
+            let mut j = skip_attrs(field, 0, &mut flip);
+            j = skip_vis(field, j);
+            let name = match field.get(j) {
+                Some(TokenTree::Ident(id)) => id.to_string(),
+                _ => return Err("expected field name".to_string()),
+            };
+            if !is_punct(field.get(j + 1), ':') {
+                return Err(format!("expected type for field '{}'", name));
+            }
+            let ty = to_string(&field[j + 2..]);
+            fields.push(Field { name, ty, flip });
+        }
+
+        Ok(Self { name, params, args, has_type_params, predicates, fields })
+    }
+
+    /// Generate the implementation of `Bundle`.
+    fn impl_bundle(&self) -> String {
+        const BUNDLE: &str = "::mafic::bundle::Bundle";
+        let params = self.params.join(", ");
+        let args = self.args.join(", ");
+
+        // Require all fields to be bundles when they depend on a type
+        let mut predicates = self.predicates.trim().to_string();
+        if self.has_type_params {
+            for f in &self.fields {
+                if !predicates.is_empty() && !predicates.ends_with(',') {
+                    predicates.push(',');
+                }
+                predicates.push_str(&format!(" {}: {}", f.ty, BUNDLE));
+            }
+        }
+
+        let mut data = String::new();
+        let mut connect = String::new();
+        let mut alias = String::new();
+        let mut vars = String::new();
+        let mut drive = Vec::new();
+        let mut sample = Vec::new();
+        let mut outputs = Vec::new();
+        for (idx, f) in self.fields.iter().enumerate() {
+            data.push_str(&format!("<{} as {}>::Data, ", f.ty, BUNDLE));
+            connect.push_str(&format!(
+                "<{} as {}>::connect_wires(&sink.{}, &source.{}, flip ^ {}, out);\n",
+                f.ty, BUNDLE, f.name, f.name, f.flip
+            ));
+            alias.push_str(&format!(
+                "{}::alias(&self.{}, &other.{}, wires);\n", BUNDLE, f.name, f.name
+            ));
+            vars.push_str(&format!("d{}, ", idx));
+            drive.push(format!(
+                "{}::drive_all(&self.{}, d{})", BUNDLE, f.name, idx
+            ));
+            sample.push(format!("{}::sample_all(&self.{})", BUNDLE, f.name));
+            outputs.push(format!("d{}", idx));
+        }
+
+        // All fields are driven/sampled concurrently
+        const JOIN: &str = "::mafic::combinator::join";
+        let drive = nest(&drive, JOIN, "async {}");
+        let sample = nest(&sample, JOIN, "async {}");
+        let pattern = nest(&outputs, "", "()");
+
+        format!("
+            impl <{params}> {BUNDLE} for {name}<{args}> where {predicates} {{
+                type Data = ({data});
+                fn connect_wires<'__mafic>(sink: &'__mafic Self,
+                    source: &'__mafic Self, flip: bool,
+                    out: &mut ::std::vec::Vec<::mafic::module::Process<'__mafic>>)
+                {{
+                    {connect}
+                }}
+                fn alias(&self, other: &Self,
+                    wires: &mut ::mafic::wire::WireMap)
+                {{
+                    {alias}
+                }}
+                async fn drive_all(&self, data: Self::Data) {{
+                    let ({vars}) = data;
+                    {drive}.await;
+                }}
+                async fn sample_all(&self) -> Self::Data {{
+                    let {pattern} = {sample}.await;
+                    ({vars})
+                }}
+            }}",
+            name = self.name,
+        )
+    }
+}
+
+fn is_ident(t: Option<&TokenTree>, s: &str) -> bool {
+    matches!(t, Some(TokenTree::Ident(id)) if id.to_string() == s)
+}
+
+fn is_punct(t: Option<&TokenTree>, c: char) -> bool {
+    matches!(t, Some(TokenTree::Punct(p)) if p.as_char() == c)
+}
+
+fn is_group(t: &TokenTree, d: Delimiter) -> bool {
+    matches!(t, TokenTree::Group(g) if g.delimiter() == d)
+}
+
+fn to_string(tokens: &[TokenTree]) -> String {
+    tokens.iter().cloned().collect::<TokenStream>().to_string()
+}
+
+/// Skip attributes starting at `i`, setting `flip` if `#[bundle(flip)]` is
+/// present.
+fn skip_attrs(tokens: &[TokenTree], mut i: usize, flip: &mut bool) -> usize {
+    while is_punct(tokens.get(i), '#') {
+        if let Some(TokenTree::Group(g)) = tokens.get(i + 1) {
+            let attr: Vec<TokenTree> = g.stream().into_iter().collect();
+            if is_ident(attr.first(), "bundle")
+                && let Some(TokenTree::Group(args)) = attr.get(1)
+            {
+                *flip |= args.stream().into_iter()
+                    .any(|t| is_ident(Some(&t), "flip"));
+            }
+        }
+        i += 2;
+    }
+    i
+}
+
+/// Skip a visibility qualifier (ie. `pub` or `pub(crate)`) starting at `i`.
+fn skip_vis(tokens: &[TokenTree], mut i: usize) -> usize {
+    if is_ident(tokens.get(i), "pub") {
+        i += 1;
+        if matches!(tokens.get(i), Some(t) if is_group(t, Delimiter::Parenthesis)) {
+            i += 1;
+        }
+    }
+    i
+}
+
+/// Return the index of the '>' matching the '<' at `start`.
+fn matching_angle(tokens: &[TokenTree], start: usize) -> Result<usize, String> {
+    let mut depth = 0;
+    for (i, t) in tokens.iter().enumerate().skip(start) {
+        match t {
+            TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+            TokenTree::Punct(p) if p.as_char() == '>' && !is_arrow(tokens, i) => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(i);
+                }
+            },
+            _ => {},
+        }
+    }
+    Err("unterminated generic parameters".to_string())
+}
+
+/// Returns 'true' if the '>' at `i` is part of a `->`.
+fn is_arrow(tokens: &[TokenTree], i: usize) -> bool {
+    i > 0 && matches!(&tokens[i - 1],
+        TokenTree::Punct(p) if p.as_char() == '-' && p.spacing() == Spacing::Joint)
+}
+
+/// Split a list of tokens on commas which are not nested in '<' and '>'.
+fn split_commas(tokens: &[TokenTree]) -> Vec<&[TokenTree]> {
+    let mut res = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, t) in tokens.iter().enumerate() {
+        match t {
+            TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+            TokenTree::Punct(p) if p.as_char() == '>' && !is_arrow(tokens, i) => {
+                depth -= 1
+            },
+            TokenTree::Punct(p) if p.as_char() == ',' && depth == 0 => {
+                res.push(&tokens[start..i]);
+                start = i + 1;
+            },
+            _ => {},
+        }
+    }
+    if start < tokens.len() {
+        res.push(&tokens[start..]);
+    }
+    res
+}
+
+/// Return the name of a generic parameter, and whether it is a type.
+fn param_name(param: &[TokenTree]) -> Result<(String, bool), String> {
+    match (param.first(), param.get(1)) {
+        (Some(TokenTree::Punct(p)), Some(TokenTree::Ident(id)))
+            if p.as_char() == '\'' => Ok((format!("'{}", id), false)),
+        (Some(TokenTree::Ident(c)), Some(TokenTree::Ident(id)))
+            if c.to_string() == "const" => Ok((id.to_string(), false)),
+        (Some(TokenTree::Ident(id)), _) => Ok((id.to_string(), true)),
+        _ => Err("unsupported generic parameter".to_string()),
+    }
+}
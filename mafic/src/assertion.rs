@@ -0,0 +1,398 @@
+//! Cycle-level assertions over the simulated state.
+//!
+//! Signals are sampled into an [`Assertions`] checker, which records a
+//! history of their values. Properties are built from conditions on those
+//! signals, and are evaluated at the end of each cycle:
+//!
+//! ```ignore
+//! let mut a = Assertions::new();
+//! let req = a.wire("req", req);
+//! let ack = a.wire("ack", ack);
+//! let full = a.wire("full", fifo.full);
+//! let empty = a.wire("empty", fifo.empty);
+//!
+//! // If 'req' is high, 'ack' must be high within 1 to 4 cycles
+//! a.assert("req_ack", req.high().implies(ack.high().within(1, 4)));
+//! // 'full' and 'empty' are never both high
+//! a.assert("full_empty", !(full.high() & empty.high()));
+//! // 'count' increases by one in the cycle after 'push' is high
+//! let (now, prev) = (count.clone(), count.past(1));
+//! a.assert("count", push.past(1).high().implies(Cond::from_fn(move || {
+//!     now.value() == prev.value().map(|c| c + 1)
+//! })));
+//! ```
+//!
+//! A condition is evaluated on the current cycle. [`Signal::past`] refers
+//! to the value of a signal some number of cycles ago. Undriven wires, and
+//! cycles before the start of the simulation, have no value: conditions
+//! on a signal without a value are always false.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::ops::{ BitAnd, BitOr, Not };
+use std::rc::Rc;
+
+use crate::check::Checker;
+use crate::engine::EngineState;
+use crate::register::RegisterId;
+use crate::wire::WireId;
+
+/// Samples the value of a signal.
+type Probe<T> = Box<dyn Fn(&EngineState) -> Option<T>>;
+
+/// The recorded values of a signal.
+struct History<T> {
+    name: String,
+    probe: Probe<T>,
+    /// Most recent value first
+    values: VecDeque<Option<T>>,
+    /// Number of values to keep
+    depth: usize,
+}
+
+/// A signal with a history of values.
+trait Tracked {
+    fn name(&self) -> String;
+    fn sample(&self, state: &EngineState);
+    /// Keep at least `depth` values.
+    fn require(&self, depth: usize);
+    /// Return the formatted values from `ago` cycles ago up to the current
+    /// cycle.
+    fn dump(&self, ago: usize) -> Vec<String>;
+}
+impl <T: Copy + fmt::Debug + 'static> Tracked for RefCell<History<T>> {
+    fn name(&self) -> String {
+        self.borrow().name.clone()
+    }
+    fn sample(&self, state: &EngineState) {
+        let mut h = self.borrow_mut();
+        let value = (h.probe)(state);
+        h.values.push_front(value);
+        let depth = h.depth;
+        h.values.truncate(depth);
+    }
+    fn require(&self, depth: usize) {
+        let mut h = self.borrow_mut();
+        h.depth = h.depth.max(depth);
+    }
+    fn dump(&self, ago: usize) -> Vec<String> {
+        let h = self.borrow();
+        (0..=ago).rev().map(|i| match h.values.get(i) {
+            Some(Some(v)) => format!("{:?}", v),
+            _ => "-".to_string(),
+        }).collect()
+    }
+}
+
+/// A signal sampled by [`Assertions`], optionally delayed by some number of
+/// cycles.
+pub struct Signal<T: Copy + fmt::Debug + 'static> {
+    history: Rc<RefCell<History<T>>>,
+    /// Number of cycles ago
+    ago: usize,
+}
+impl <T: Copy + fmt::Debug + 'static> Clone for Signal<T> {
+    fn clone(&self) -> Self {
+        Self { history: self.history.clone(), ago: self.ago }
+    }
+}
+impl <T: Copy + fmt::Debug + 'static> Signal<T> {
+    fn tracked(&self) -> Rc<dyn Tracked> {
+        self.history.clone()
+    }
+
+    /// Return the value of this signal (if any).
+    pub fn value(&self) -> Option<T> {
+        self.history.borrow().values.get(self.ago).copied().flatten()
+    }
+
+    /// Refer to the value of this signal `n` cycles ago (like `$past`).
+    pub fn past(&self, n: usize) -> Self {
+        self.history.require(self.ago + n + 1);
+        Self { history: self.history.clone(), ago: self.ago + n }
+    }
+
+    /// True when the value satisfies a predicate.
+    pub fn is(&self, f: impl Fn(T) -> bool + 'static) -> Cond {
+        let sig = self.clone();
+        Cond::new(vec![self.tracked()], move || sig.value().is_some_and(&f))
+    }
+
+    /// True when the value is equal to `value`.
+    pub fn eq(&self, value: T) -> Cond where T: PartialEq {
+        self.is(move |v| v == value)
+    }
+
+    /// True when the value differs from the previous cycle.
+    pub fn changed(&self) -> Cond where T: PartialEq {
+        let (cur, prev) = (self.clone(), self.past(1));
+        Cond::new(vec![self.tracked()], move || {
+            cur.value().is_some() && cur.value() != prev.value()
+        })
+    }
+
+    /// True when the value is the same as in the previous cycle.
+    pub fn stable(&self) -> Cond where T: PartialEq {
+        let (cur, prev) = (self.clone(), self.past(1));
+        Cond::new(vec![self.tracked()], move || {
+            cur.value().is_some() && cur.value() == prev.value()
+        })
+    }
+}
+impl Signal<bool> {
+    /// True when the signal is high.
+    pub fn high(&self) -> Cond { self.eq(true) }
+
+    /// True when the signal is low.
+    pub fn low(&self) -> Cond { self.eq(false) }
+
+    /// True when the signal is high, and was not high in the previous cycle
+    /// (like `$rose`).
+    pub fn rose(&self) -> Cond { self.high() & !self.past(1).high() }
+
+    /// True when the signal is low, and was high in the previous cycle
+    /// (like `$fell`).
+    pub fn fell(&self) -> Cond { self.low() & self.past(1).high() }
+}
+
+/// A boolean condition on the current cycle.
+#[derive(Clone)]
+pub struct Cond {
+    eval: Rc<dyn Fn() -> bool>,
+    /// Signals included in failure reports
+    signals: Vec<Rc<dyn Tracked>>,
+}
+impl Cond {
+    fn new(signals: Vec<Rc<dyn Tracked>>, f: impl Fn() -> bool + 'static) -> Self {
+        Self { eval: Rc::new(f), signals }
+    }
+
+    /// Create a condition from a closure (ie. comparing the values of
+    /// several signals).
+    pub fn from_fn(f: impl Fn() -> bool + 'static) -> Self {
+        Self::new(Vec::new(), f)
+    }
+
+    /// Evaluate this condition.
+    pub fn eval(&self) -> bool {
+        (self.eval)()
+    }
+
+    /// A sequence where this condition holds exactly `n` cycles later
+    /// (like `##n`).
+    pub fn delay(self, n: usize) -> Sequence {
+        self.within(n, n)
+    }
+
+    /// A sequence where this condition holds in at least one cycle between
+    /// `lo` and `hi` cycles later (like `##[lo:hi]`).
+    pub fn within(self, lo: usize, hi: usize) -> Sequence {
+        assert!(lo <= hi, "invalid delay range {}..={}", lo, hi);
+        Sequence { cond: self, lo, hi }
+    }
+
+    /// A property where the sequence must follow in every cycle where this
+    /// condition holds (like `|->`).
+    pub fn implies(self, seq: impl Into<Sequence>) -> Property {
+        Property { antecedent: Some(self), consequent: seq.into() }
+    }
+
+    fn combine(self, rhs: Cond, f: impl Fn(bool, &Cond) -> bool + 'static) -> Cond {
+        let mut signals = self.signals.clone();
+        signals.extend(rhs.signals.iter().cloned());
+        let lhs = self;
+        Cond::new(signals, move || f(lhs.eval(), &rhs))
+    }
+}
+impl BitAnd for Cond {
+    type Output = Cond;
+    fn bitand(self, rhs: Cond) -> Cond {
+        self.combine(rhs, |l, r| l && r.eval())
+    }
+}
+impl BitOr for Cond {
+    type Output = Cond;
+    fn bitor(self, rhs: Cond) -> Cond {
+        self.combine(rhs, |l, r| l || r.eval())
+    }
+}
+impl Not for Cond {
+    type Output = Cond;
+    fn not(self) -> Cond {
+        let signals = self.signals.clone();
+        Cond::new(signals, move || !self.eval())
+    }
+}
+
+/// A condition which must hold in at least one cycle of a window.
+#[derive(Clone)]
+pub struct Sequence {
+    cond: Cond,
+    /// The first cycle of the window (relative to the start)
+    lo: usize,
+    /// The last cycle of the window (relative to the start)
+    hi: usize,
+}
+impl From<Cond> for Sequence {
+    fn from(cond: Cond) -> Self {
+        cond.delay(0)
+    }
+}
+
+/// A property checked by [`Assertions`].
+///
+/// An attempt starts on every cycle where the antecedent holds (or on
+/// every cycle, without an antecedent), and fails when the consequent does
+/// not hold in any cycle of its window.
+#[derive(Clone)]
+pub struct Property {
+    antecedent: Option<Cond>,
+    consequent: Sequence,
+}
+impl From<Cond> for Property {
+    fn from(cond: Cond) -> Self {
+        Self { antecedent: None, consequent: cond.into() }
+    }
+}
+impl From<Sequence> for Property {
+    fn from(seq: Sequence) -> Self {
+        Self { antecedent: None, consequent: seq }
+    }
+}
+
+/// A property which failed.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AssertionFailure {
+    /// The name of the property
+    pub name: String,
+    /// The cycle in which the failing attempt started
+    pub start: usize,
+    /// The cycle in which the attempt failed
+    pub cycle: usize,
+    /// The formatted values of each signal in the property, for every cycle
+    /// from `start` to `cycle`
+    pub trace: Vec<(String, Vec<String>)>,
+}
+impl fmt::Display for AssertionFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "assertion '{}' failed at cycle {} (started at cycle {})",
+            self.name, self.cycle, self.start)?;
+        for (name, values) in &self.trace {
+            writeln!(f, "  {}: {}", name, values.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// A property, and the start of each attempt which has not completed yet.
+struct Assertion {
+    name: String,
+    property: Property,
+    signals: Vec<Rc<dyn Tracked>>,
+    pending: VecDeque<usize>,
+}
+
+/// Evaluates properties at the end of each cycle.
+///
+/// Signals are sampled after all tasks have completed (see [`Checker`]),
+/// so registers have the value from the start of the cycle.
+#[derive(Default)]
+pub struct Assertions {
+    signals: Vec<Rc<dyn Tracked>>,
+    assertions: Vec<Assertion>,
+    /// Failures observed so far
+    pub failures: Vec<AssertionFailure>,
+}
+impl Assertions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn track<T: Copy + fmt::Debug + 'static>(&mut self, name: String,
+        probe: Probe<T>) -> Signal<T>
+    {
+        let history = Rc::new(RefCell::new(History {
+            name, probe, values: VecDeque::new(), depth: 1,
+        }));
+        self.signals.push(history.clone());
+        Signal { history, ago: 0 }
+    }
+
+    /// Sample a wire on every cycle.
+    pub fn wire<T: Copy + fmt::Debug + 'static>(&mut self,
+        name: impl Into<String>, wire: WireId<T>) -> Signal<T>
+    {
+        self.track(name.into(), Box::new(move |state| state.wires.peek_wire(wire)))
+    }
+
+    /// Sample a register on every cycle.
+    pub fn register<T: Copy + fmt::Debug + 'static>(&mut self,
+        name: impl Into<String>, reg: RegisterId<T>) -> Signal<T>
+    {
+        self.track(name.into(), Box::new(move |state| {
+            Some(state.registers.peek_register(reg))
+        }))
+    }
+
+    /// Check a property on every cycle.
+    pub fn assert(&mut self, name: impl Into<String>, property: impl Into<Property>) {
+        let property = property.into();
+        let mut signals: Vec<Rc<dyn Tracked>> = Vec::new();
+        let conds = property.antecedent.iter().chain([&property.consequent.cond]);
+        for s in conds.flat_map(|c| c.signals.iter()) {
+            if !signals.iter().any(|t| Rc::ptr_eq(t, s)) {
+                // Keep enough history to report the whole window
+                s.require(property.consequent.hi + 1);
+                signals.push(s.clone());
+            }
+        }
+        self.assertions.push(Assertion {
+            name: name.into(), property, signals, pending: VecDeque::new(),
+        });
+    }
+
+    /// Return the number of attempts which have not completed yet.
+    pub fn pending(&self) -> usize {
+        self.assertions.iter().map(|a| a.pending.len()).sum()
+    }
+}
+impl Checker for Assertions {
+    fn check(&mut self, cycle: usize, state: &EngineState) {
+        for s in &self.signals {
+            s.sample(state);
+        }
+        for a in self.assertions.iter_mut() {
+            let Property { antecedent, consequent: seq } = &a.property;
+            if antecedent.as_ref().is_none_or(|c| c.eval()) {
+                a.pending.push_back(cycle);
+            }
+            if a.pending.is_empty() {
+                continue;
+            }
+
+            // Every pending attempt shares the same condition on this cycle
+            let holds = seq.cond.eval();
+            let mut remaining = VecDeque::new();
+            for start in a.pending.drain(..) {
+                let offset = cycle - start;
+                if offset >= seq.lo && holds {
+                    continue;
+                }
+                if offset == seq.hi {
+                    self.failures.push(AssertionFailure {
+                        name: a.name.clone(),
+                        start,
+                        cycle,
+                        trace: a.signals.iter()
+                            .map(|s| (s.name(), s.dump(offset)))
+                            .collect(),
+                    });
+                    continue;
+                }
+                remaining.push_back(start);
+            }
+            a.pending = remaining;
+        }
+    }
+}
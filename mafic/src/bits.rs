@@ -0,0 +1,385 @@
+//! Fixed-width bit-vector types with hardware semantics.
+//!
+//! [`Bits<N>`] is an unsigned `N`-bit value, and [`SInt<N>`] is a signed
+//! (two's complement) `N`-bit value. Both are limited to 128 bits.
+//!
+//! Unlike native Rust integers, arithmetic on these types always wraps
+//! around at the width of the type (like the equivalent logic in hardware)
+//! instead of panicking on overflow.
+//!
+//! Operations that change the width of a value (ie. slicing, concatenation,
+//! and extension) take the width of the result as a const parameter.
+//! The widths are checked at compile time (only the offset of a slice is
+//! checked when the operation is performed).
+//!
+//! Division by zero is defined like in RISC-V: the quotient has all bits
+//! set (ie. `-1` for signed values), and the remainder is the dividend.
+
+use std::fmt;
+use std::ops::*;
+
+/// Trait implemented on types with a known width in bits.
+pub trait BitWidth {
+    /// The width of this type in bits
+    const WIDTH: usize;
+}
+macro_rules! impl_bitwidth_native {
+    ($($t:ty),*) => { $(
+        impl BitWidth for $t { const WIDTH: usize = <$t>::BITS as usize; }
+    )* };
+}
+impl_bitwidth_native!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+impl BitWidth for bool { const WIDTH: usize = 1; }
+impl <const N: usize> BitWidth for Bits<N> { const WIDTH: usize = N; }
+impl <const N: usize> BitWidth for SInt<N> { const WIDTH: usize = N; }
+
+/// Returns a mask with the low `n` bits set.
+const fn mask(n: usize) -> u128 {
+    if n >= 128 { u128::MAX } else { (1 << n) - 1 }
+}
+
+/// An unsigned `N`-bit value.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Bits<const N: usize>(u128);
+
+impl <const N: usize> Bits<N> {
+    /// The width of this type in bits
+    pub const WIDTH: usize = N;
+
+    /// Mask for the valid bits in this type
+    pub const MASK: u128 = {
+        assert!(N > 0 && N <= 128, "Bits<N> must be 1 to 128 bits wide");
+        mask(N)
+    };
+
+    /// The value with all bits cleared
+    pub const ZERO: Self = Self(0);
+
+    /// The value with all bits set
+    pub const ONES: Self = Self(Self::MASK);
+
+    /// Create a new value, truncating `value` to `N` bits.
+    pub const fn new(value: u128) -> Self {
+        Self(value & Self::MASK)
+    }
+
+    /// Create a new value, or return `None` if `value` is wider than `N`
+    /// bits.
+    pub const fn try_new(value: u128) -> Option<Self> {
+        if value & !Self::MASK == 0 { Some(Self(value)) } else { None }
+    }
+
+    /// Return the value as a native integer.
+    pub const fn value(self) -> u128 { self.0 }
+
+    /// Return the value of bit `idx`.
+    pub fn bit(self, idx: usize) -> bool {
+        assert!(idx < N, "bit {} out of range for Bits<{}>", idx, N);
+        (self.0 >> idx) & 1 != 0
+    }
+
+    /// Return a copy of this value with bit `idx` set to `value`.
+    pub fn with_bit(self, idx: usize, value: bool) -> Self {
+        assert!(idx < N, "bit {} out of range for Bits<{}>", idx, N);
+        Self((self.0 & !(1 << idx)) | ((value as u128) << idx))
+    }
+
+    /// Return the `M` bits starting at bit `lo` (ie. `self[lo+M-1:lo]`).
+    pub fn slice<const M: usize>(self, lo: usize) -> Bits<M> {
+        const { assert!(M <= N, "slice is wider than the value") };
+        assert!(lo + M <= N, "slice [{}:{}] out of range for Bits<{}>",
+            lo + M - 1, lo, N);
+        Bits::new(self.0 >> lo)
+    }
+
+    /// Concatenate with `lo`, where `self` becomes the most-significant
+    /// bits of the result (ie. `{self, lo}`).
+    pub fn concat<const M: usize, const R: usize>(self, lo: Bits<M>)
+        -> Bits<R>
+    {
+        const { assert!(R == N + M, "width of concatenation is not the sum \
+            of the widths") };
+        Bits::new((self.0 << M) | lo.0)
+    }
+
+    /// Zero-extend to `M` bits.
+    pub fn zext<const M: usize>(self) -> Bits<M> {
+        const { assert!(M >= N, "cannot extend to a narrower width") };
+        Bits::new(self.0)
+    }
+
+    /// Sign-extend to `M` bits.
+    pub fn sext<const M: usize>(self) -> Bits<M> {
+        const { assert!(M >= N, "cannot extend to a narrower width") };
+        Bits::new(self.as_signed().value() as u128)
+    }
+
+    /// Truncate to the low `M` bits.
+    pub fn trunc<const M: usize>(self) -> Bits<M> {
+        const { assert!(M <= N, "cannot truncate to a wider width") };
+        Bits::new(self.0)
+    }
+
+    /// Reinterpret as a signed value.
+    pub const fn as_signed(self) -> SInt<N> { SInt(self.0) }
+
+    /// AND of all bits.
+    pub fn and_reduce(self) -> bool { self.0 == Self::MASK }
+
+    /// OR of all bits.
+    pub fn or_reduce(self) -> bool { self.0 != 0 }
+
+    /// XOR of all bits (ie. parity).
+    pub fn xor_reduce(self) -> bool { self.0.count_ones() & 1 != 0 }
+
+    /// Return the number of set bits.
+    pub fn count_ones(self) -> u32 { self.0.count_ones() }
+}
+
+/// A signed (two's complement) `N`-bit value.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SInt<const N: usize>(u128);
+
+impl <const N: usize> SInt<N> {
+    /// The width of this type in bits
+    pub const WIDTH: usize = N;
+
+    /// The smallest representable value
+    pub const MIN: Self = Self(1 << (N - 1));
+
+    /// The largest representable value
+    pub const MAX: Self = Self(Bits::<N>::MASK >> 1);
+
+    /// Create a new value, truncating `value` to `N` bits.
+    pub const fn new(value: i128) -> Self {
+        Self(value as u128 & Bits::<N>::MASK)
+    }
+
+    /// Create a new value, or return `None` if `value` is not representable
+    /// with `N` bits.
+    pub fn try_new(value: i128) -> Option<Self> {
+        let res = Self::new(value);
+        if res.value() == value { Some(res) } else { None }
+    }
+
+    /// Return the value as a native integer.
+    pub const fn value(self) -> i128 {
+        let shift = 128 - N as u32;
+        ((self.0 << shift) as i128) >> shift
+    }
+
+    /// Return the value of bit `idx`.
+    pub fn bit(self, idx: usize) -> bool { self.as_bits().bit(idx) }
+
+    /// Returns 'true' if this value is negative.
+    pub fn is_negative(self) -> bool { self.bit(N - 1) }
+
+    /// Return the `M` bits starting at bit `lo` (ie. `self[lo+M-1:lo]`).
+    pub fn slice<const M: usize>(self, lo: usize) -> Bits<M> {
+        self.as_bits().slice(lo)
+    }
+
+    /// Sign-extend to `M` bits.
+    pub fn sext<const M: usize>(self) -> SInt<M> {
+        self.as_bits().sext::<M>().as_signed()
+    }
+
+    /// Truncate to the low `M` bits.
+    pub fn trunc<const M: usize>(self) -> SInt<M> {
+        self.as_bits().trunc::<M>().as_signed()
+    }
+
+    /// Reinterpret as an unsigned value.
+    pub const fn as_bits(self) -> Bits<N> { Bits(self.0) }
+}
+impl <const N: usize> PartialOrd for SInt<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl <const N: usize> Ord for SInt<N> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.value().cmp(&other.value())
+    }
+}
+
+// Arithmetic and logic operators which wrap around at the width of the type
+macro_rules! impl_binop {
+    ($ty:ident, $Op:ident, $op:ident, $OpAssign:ident, $op_assign:ident,
+     |$a:ident, $b:ident| $e:expr) => {
+        impl <const N: usize> $Op for $ty<N> {
+            type Output = Self;
+            fn $op(self, rhs: Self) -> Self {
+                let ($a, $b) = (self.0, rhs.0);
+                Self(Bits::<N>::new($e).0)
+            }
+        }
+        impl <const N: usize> $OpAssign for $ty<N> {
+            fn $op_assign(&mut self, rhs: Self) { *self = $Op::$op(*self, rhs); }
+        }
+    };
+}
+macro_rules! impl_common_ops {
+    ($ty:ident) => {
+        impl_binop!($ty, Add, add, AddAssign, add_assign, |a, b| a.wrapping_add(b));
+        impl_binop!($ty, Sub, sub, SubAssign, sub_assign, |a, b| a.wrapping_sub(b));
+        impl_binop!($ty, Mul, mul, MulAssign, mul_assign, |a, b| a.wrapping_mul(b));
+        impl_binop!($ty, BitAnd, bitand, BitAndAssign, bitand_assign, |a, b| a & b);
+        impl_binop!($ty, BitOr, bitor, BitOrAssign, bitor_assign, |a, b| a | b);
+        impl_binop!($ty, BitXor, bitxor, BitXorAssign, bitxor_assign, |a, b| a ^ b);
+
+        impl <const N: usize> Not for $ty<N> {
+            type Output = Self;
+            fn not(self) -> Self { Self(!self.0 & Bits::<N>::MASK) }
+        }
+        impl <const N: usize> Shl<usize> for $ty<N> {
+            type Output = Self;
+            fn shl(self, rhs: usize) -> Self {
+                if rhs >= N { Self(0) } else { Self((self.0 << rhs) & Bits::<N>::MASK) }
+            }
+        }
+    };
+}
+impl_common_ops!(Bits);
+impl_common_ops!(SInt);
+
+impl <const N: usize> Div for Bits<N> {
+    type Output = Self;
+    /// Division by zero returns [`Bits::ONES`]
+    fn div(self, rhs: Self) -> Self { 
+        self.0.checked_div(rhs.0).map_or(Self::ONES, Self)
+    }
+}
+impl <const N: usize> Rem for Bits<N> {
+    type Output = Self;
+    /// Division by zero returns the dividend
+    fn rem(self, rhs: Self) -> Self { 
+        self.0.checked_rem(rhs.0).map_or(self, Self)
+    }
+}
+impl <const N: usize> Shr<usize> for Bits<N> {
+    type Output = Self;
+    /// Logical shift right
+    fn shr(self, rhs: usize) -> Self {
+        if rhs >= N { Self(0) } else { Self(self.0 >> rhs) }
+    }
+}
+impl <const N: usize> Div for SInt<N> {
+    type Output = Self;
+    /// Division by zero returns `-1`
+    fn div(self, rhs: Self) -> Self {
+        if rhs.0 == 0 { return Self::new(-1); }
+        Self::new(self.value().wrapping_div(rhs.value()))
+    }
+}
+impl <const N: usize> Rem for SInt<N> {
+    type Output = Self;
+    /// Division by zero returns the dividend
+    fn rem(self, rhs: Self) -> Self {
+        if rhs.0 == 0 { return self; }
+        Self::new(self.value().wrapping_rem(rhs.value()))
+    }
+}
+impl <const N: usize> Shr<usize> for SInt<N> {
+    type Output = Self;
+    /// Arithmetic shift right
+    fn shr(self, rhs: usize) -> Self {
+        Self::new(self.value() >> rhs.min(N - 1))
+    }
+}
+impl <const N: usize> Neg for SInt<N> {
+    type Output = Self;
+    fn neg(self) -> Self { Self::new(self.value().wrapping_neg()) }
+}
+
+// Conversions to/from native integers
+
+impl <const N: usize> From<bool> for Bits<N> {
+    fn from(value: bool) -> Self { Self::new(value as u128) }
+}
+impl From<Bits<1>> for bool {
+    fn from(value: Bits<1>) -> Self { value.0 != 0 }
+}
+impl <const N: usize> From<Bits<N>> for u128 {
+    fn from(value: Bits<N>) -> Self { value.0 }
+}
+impl <const N: usize> From<SInt<N>> for i128 {
+    fn from(value: SInt<N>) -> Self { value.value() }
+}
+
+/// Error returned when a value does not fit in the target type.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct WidthError;
+impl fmt::Display for WidthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "value does not fit in the target width")
+    }
+}
+impl std::error::Error for WidthError {}
+
+macro_rules! impl_native_conversions {
+    ($($u:ty, $i:ty);*) => { $(
+        impl_native_conversions!(@to_bits $u, $i);
+        impl <const N: usize> TryFrom<Bits<N>> for $u {
+            type Error = WidthError;
+            fn try_from(value: Bits<N>) -> Result<Self, WidthError> {
+                <$u>::try_from(value.0).map_err(|_| WidthError)
+            }
+        }
+        impl <const N: usize> TryFrom<SInt<N>> for $i {
+            type Error = WidthError;
+            fn try_from(value: SInt<N>) -> Result<Self, WidthError> {
+                <$i>::try_from(value.value()).map_err(|_| WidthError)
+            }
+        }
+    )* };
+    (@to_bits $u:ty, $i:ty) => {
+        impl <const N: usize> TryFrom<$u> for Bits<N> {
+            type Error = WidthError;
+            fn try_from(value: $u) -> Result<Self, WidthError> {
+                Self::try_new(value as u128).ok_or(WidthError)
+            }
+        }
+        impl <const N: usize> TryFrom<$i> for SInt<N> {
+            type Error = WidthError;
+            fn try_from(value: $i) -> Result<Self, WidthError> {
+                Self::try_new(value as i128).ok_or(WidthError)
+            }
+        }
+    };
+}
+impl_native_conversions!(u8, i8; u16, i16; u32, i32; u64, i64);
+impl_native_conversions!(@to_bits u128, i128);
+
+// Formatting (ie. Verilog-style literals)
+
+impl <const N: usize> fmt::Debug for Bits<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}'h{:x}", N, self.0)
+    }
+}
+impl <const N: usize> fmt::Display for Bits<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+impl <const N: usize> fmt::LowerHex for Bits<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+impl <const N: usize> fmt::Binary for Bits<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Binary::fmt(&self.0, f)
+    }
+}
+impl <const N: usize> fmt::Debug for SInt<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}'sd{}", N, self.value())
+    }
+}
+impl <const N: usize> fmt::Display for SInt<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.value(), f)
+    }
+}
@@ -0,0 +1,149 @@
+//! Bundles of wires (ie. interfaces/ports).
+//!
+//! A [`Bundle`] is a group of wires which are connected together as a unit.
+//! The trait is implemented on [`WireId`], arrays and tuples of bundles,
+//! and can be derived on structs whose fields are all bundles:
+//!
+//! ```ignore
+//! #[derive(Bundle)]
+//! pub struct ReadPort {
+//!     req: ReadPortReq,
+//!     #[bundle(flip)]
+//!     resp: ReadPortResp,
+//! }
+//! ```
+//!
+//! Fields marked with `#[bundle(flip)]` flow in the opposite direction
+//! (ie. responses flowing back to the requester). Tuples of bundles are 
+//! also bundles.
+
+use std::future::Future;
+
+use crate::combinator::{ Join, join_all };
+use crate::module::Process;
+use crate::wire::{ WireId, WireMap };
+
+/// Trait implemented on groups of wires which are connected as a unit.
+pub trait Bundle {
+    /// The values carried by all wires in this bundle
+    type Data;
+
+    /// Collect a future assigning each wire in `sink` from the matching
+    /// wire in `source` (or the other way around, when `flip` is set).
+    fn connect_wires<'a>(sink: &'a Self, source: &'a Self, flip: bool,
+        out: &mut Vec<Process<'a>>);
+
+    /// Merge each wire in this bundle with the matching wire in `other` 
+    /// into a single net (see [`WireMap::connect`]). 
+    ///
+    /// Unlike [`Bundle::connect`], this happens during elaboration and 
+    /// costs nothing while simulating. 
+    fn alias(&self, other: &Self, wires: &mut WireMap);
+
+    /// Drive all wires in this bundle concurrently.
+    async fn drive_all(&self, data: Self::Data);
+
+    /// Wait for all wires in this bundle to be driven (in any order), and 
+    /// return their values.
+    async fn sample_all(&self) -> Self::Data;
+
+    /// Connect this bundle to `source`.
+    ///
+    /// Each wire in this bundle is driven by the matching wire in `source`,
+    /// except for flipped wires, which drive the matching wire in `source`.
+    /// All wires are assigned concurrently.
+    fn connect<'a>(&'a self, source: &'a Self) -> impl Future<Output = ()> + 'a
+        where Self: Sized
+    {
+        let mut out = Vec::new();
+        Self::connect_wires(self, source, false, &mut out);
+        async move { join_all(out).await; }
+    }
+}
+
+impl <T: Copy + std::fmt::Debug + 'static> Bundle for WireId<T> {
+    type Data = T;
+
+    fn connect_wires<'a>(sink: &'a Self, source: &'a Self, flip: bool,
+        out: &mut Vec<Process<'a>>)
+    {
+        if flip {
+            out.push(Box::pin(source.assign(*sink)));
+        } else {
+            out.push(Box::pin(sink.assign(*source)));
+        }
+    }
+
+    fn alias(&self, other: &Self, wires: &mut WireMap) {
+        wires.connect(*self, *other);
+    }
+
+    async fn drive_all(&self, data: T) {
+        self.drive(data).await
+    }
+
+    async fn sample_all(&self) -> T {
+        self.sample().await
+    }
+}
+
+impl <B: Bundle, const N: usize> Bundle for [B; N] {
+    type Data = [B::Data; N];
+
+    fn connect_wires<'a>(sink: &'a Self, source: &'a Self, flip: bool,
+        out: &mut Vec<Process<'a>>)
+    {
+        for (s, t) in sink.iter().zip(source.iter()) {
+            B::connect_wires(s, t, flip, out);
+        }
+    }
+
+    fn alias(&self, other: &Self, wires: &mut WireMap) {
+        for (s, t) in self.iter().zip(other.iter()) {
+            s.alias(t, wires);
+        }
+    }
+
+    async fn drive_all(&self, data: Self::Data) {
+        join_all(self.iter().zip(data).map(|(b, d)| b.drive_all(d))).await;
+    }
+
+    async fn sample_all(&self) -> Self::Data {
+        self.each_ref().map(|b| b.sample_all()).join().await
+    }
+}
+
+macro_rules! impl_bundle_tuple {
+    ($($B:ident $b:ident $idx:tt),+) => {
+        impl <$($B: Bundle),+> Bundle for ($($B,)+) {
+            type Data = ($($B::Data,)+);
+
+            fn connect_wires<'a>(sink: &'a Self, source: &'a Self, flip: bool,
+                out: &mut Vec<Process<'a>>)
+            {
+                $( $B::connect_wires(&sink.$idx, &source.$idx, flip, out); )+
+            }
+
+            fn alias(&self, other: &Self, wires: &mut WireMap) {
+                $( self.$idx.alias(&other.$idx, wires); )+
+            }
+
+            async fn drive_all(&self, data: Self::Data) {
+                let ($($b,)+) = data;
+                ($(self.$idx.drive_all($b),)+).join().await;
+            }
+
+            async fn sample_all(&self) -> Self::Data {
+                ($(self.$idx.sample_all(),)+).join().await
+            }
+        }
+    };
+}
+impl_bundle_tuple!(A a 0);
+impl_bundle_tuple!(A a 0, B b 1);
+impl_bundle_tuple!(A a 0, B b 1, C c 2);
+impl_bundle_tuple!(A a 0, B b 1, C c 2, D d 3);
+impl_bundle_tuple!(A a 0, B b 1, C c 2, D d 3, E e 4);
+impl_bundle_tuple!(A a 0, B b 1, C c 2, D d 3, E e 4, F f 5);
+impl_bundle_tuple!(A a 0, B b 1, C c 2, D d 3, E e 4, F f 5, G g 6);
+impl_bundle_tuple!(A a 0, B b 1, C c 2, D d 3, E e 4, F f 5, G g 6, H h 7);
@@ -0,0 +1,307 @@
+//! Clock-domain-crossing (CDC) primitives.
+//!
+//! These modules move signals between [clock domains](crate::clock), with
+//! the latency of the equivalent circuit in each domain:
+//!
+//! - [`Synchronizer`] is a two-flop synchronizer for a level signal
+//! - [`PulseSync`] carries single-cycle pulses with a toggle synchronizer
+//! - [`HandshakeSync`] carries values with a req/ack handshake
+//! - [`AsyncFifo`] is a FIFO with gray-coded pointers
+//!
+//! The primitives must be scheduled on every tick with
+//! [`Engine::schedule_module`](crate::engine::Engine::schedule_module).
+//! Their ports follow the clock domain on each side: for instance, `enq`
+//! on an [`AsyncFifo`] can only transfer a value on ticks where the write
+//! clock has an edge.
+//!
+//! [`CdcChecker`] flags logic sampling signals from another clock domain
+//! without using one of these primitives.
+
+use crate::check::Checker;
+use crate::clock::{ DomainCrossing, DomainId };
+use crate::decoupled::Decoupled;
+use crate::engine::EngineState;
+use crate::module::{InstanceId, ModuleLike};
+use crate::register::RegisterId;
+use crate::wire::WireId;
+
+/// A two-flop synchronizer.
+///
+/// `output` follows `input` after two edges of the destination clock.
+pub struct Synchronizer<T: Copy + std::fmt::Debug + 'static> {
+    pub input: WireId<T>,
+    pub output: WireId<T>,
+    ff: [RegisterId<T>; 2],
+    instance: InstanceId,
+}
+impl <T: Copy + Default + std::fmt::Debug + 'static> Synchronizer<T> {
+    /// Create a synchronizer into the `dst` clock domain.
+    pub fn new(state: &mut EngineState, dst: DomainId) -> Self {
+        Self {
+            input: state.wires.alloc(),
+            output: state.wires.alloc(),
+            ff: std::array::from_fn(|_| state.registers.alloc_in(dst, T::default())),
+            instance: state.alloc_instance::<Self>(),
+        }
+    }
+}
+impl <T: Copy + Default + std::fmt::Debug + 'static> ModuleLike
+    for Synchronizer<T>
+{
+    fn new_instance(state: &mut EngineState) -> Self {
+        Self::new(state, DomainId::DEFAULT)
+    }
+    fn instance(&self) -> Option<InstanceId> { Some(self.instance) }
+    async fn run(&self) {
+        let ff = [self.ff[0].sample().await, self.ff[1].sample().await];
+        self.output.drive(ff[1]).await;
+        self.ff[1].drive(ff[0]).await;
+        self.ff[0].drive(self.input.sample().await).await;
+    }
+}
+
+/// A toggle synchronizer for single-cycle pulses.
+///
+/// A pulse on `input` (sampled on an edge of the source clock) becomes a
+/// single-cycle pulse on `output` after two or three edges of the
+/// destination clock. Pulses must be separated by enough source cycles
+/// for the destination to observe each toggle.
+pub struct PulseSync {
+    pub input: WireId<bool>,
+    pub output: WireId<bool>,
+    toggle: RegisterId<bool>,
+    ff: [RegisterId<bool>; 3],
+    instance: InstanceId,
+}
+impl PulseSync {
+    /// Create a pulse synchronizer from the `src` to the `dst` clock domain.
+    pub fn new(state: &mut EngineState, src: DomainId, dst: DomainId) -> Self {
+        Self {
+            input: state.wires.alloc(),
+            output: state.wires.alloc(),
+            toggle: state.registers.alloc_in(src, false),
+            ff: std::array::from_fn(|_| state.registers.alloc_in(dst, false)),
+            instance: state.alloc_instance::<Self>(),
+        }
+    }
+}
+impl ModuleLike for PulseSync {
+    fn new_instance(state: &mut EngineState) -> Self {
+        Self::new(state, DomainId::DEFAULT, DomainId::DEFAULT)
+    }
+    fn instance(&self) -> Option<InstanceId> { Some(self.instance) }
+    async fn run(&self) {
+        let toggle = self.toggle.sample().await;
+        let ff = [
+            self.ff[0].sample().await,
+            self.ff[1].sample().await,
+            self.ff[2].sample().await,
+        ];
+        self.output.drive(ff[1] ^ ff[2]).await;
+        self.ff[2].drive(ff[1]).await;
+        self.ff[1].drive(ff[0]).await;
+        self.ff[0].drive(toggle).await;
+        if self.input.sample().await {
+            self.toggle.drive(!toggle).await;
+        }
+    }
+}
+
+/// Carries values between clock domains with a req/ack handshake.
+///
+/// A value accepted on `enq` is held in the source domain while the
+/// request toggles through a synchronizer into the destination domain.
+/// After the value is accepted on `deq`, the acknowledgement toggles back
+/// through a synchronizer into the source domain before `enq` is ready
+/// again.
+pub struct HandshakeSync<T: Copy + std::fmt::Debug + 'static> {
+    pub enq: Decoupled<T>,
+    pub deq: Decoupled<T>,
+    src: DomainId,
+    dst: DomainId,
+    data: RegisterId<T>,
+    req: RegisterId<bool>,
+    req_sync: [RegisterId<bool>; 2],
+    ack: RegisterId<bool>,
+    ack_sync: [RegisterId<bool>; 2],
+    instance: InstanceId,
+}
+impl <T: Copy + Default + std::fmt::Debug + 'static> HandshakeSync<T> {
+    /// Create a handshake synchronizer from the `src` to the `dst` clock
+    /// domain.
+    pub fn new(state: &mut EngineState, src: DomainId, dst: DomainId) -> Self {
+        Self {
+            enq: Decoupled::new(&mut state.wires),
+            deq: Decoupled::new(&mut state.wires),
+            src,
+            dst,
+            data: state.registers.alloc_in(src, T::default()),
+            req: state.registers.alloc_in(src, false),
+            req_sync: std::array::from_fn(|_| state.registers.alloc_in(dst, false)),
+            ack: state.registers.alloc_in(dst, false),
+            ack_sync: std::array::from_fn(|_| state.registers.alloc_in(src, false)),
+            instance: state.alloc_instance::<Self>(),
+        }
+    }
+}
+impl <T: Copy + Default + std::fmt::Debug + 'static> ModuleLike
+    for HandshakeSync<T>
+{
+    fn new_instance(state: &mut EngineState) -> Self {
+        Self::new(state, DomainId::DEFAULT, DomainId::DEFAULT)
+    }
+    fn instance(&self) -> Option<InstanceId> { Some(self.instance) }
+    async fn run(&self) {
+        let req = self.req.sample().await;
+        let ack = self.ack.sample().await;
+        let req_sync = [self.req_sync[0].sample().await, self.req_sync[1].sample().await];
+        let ack_sync = [self.ack_sync[0].sample().await, self.ack_sync[1].sample().await];
+
+        // Source domain: idle when the last request has been acknowledged
+        let enq_ready = req == ack_sync[1] && self.src.is_edge().await;
+        self.enq.ready.drive(enq_ready).await;
+
+        // Destination domain: a request is pending until acknowledged
+        let deq_valid = req_sync[1] != ack && self.dst.is_edge().await;
+        self.deq.valid.drive(deq_valid).await;
+        if deq_valid {
+            self.deq.data.drive(self.data.sample().await).await;
+        }
+
+        self.req_sync[1].drive(req_sync[0]).await;
+        self.req_sync[0].drive(req).await;
+        self.ack_sync[1].drive(ack_sync[0]).await;
+        self.ack_sync[0].drive(ack).await;
+
+        if enq_ready && self.enq.valid.sample().await {
+            self.data.drive(self.enq.data.sample().await).await;
+            self.req.drive(!req).await;
+        }
+        if deq_valid && self.deq.ready.sample().await {
+            self.ack.drive(!ack).await;
+        }
+    }
+}
+
+/// Convert a binary value to gray code.
+fn to_gray(x: usize) -> usize { x ^ (x >> 1) }
+
+/// Convert a gray-coded value to binary.
+fn from_gray(mut x: usize) -> usize {
+    let mut res = x;
+    while x != 0 {
+        x >>= 1;
+        res ^= x;
+    }
+    res
+}
+
+/// An asynchronous FIFO with `DEPTH` entries (a power of two).
+///
+/// The read and write pointers are passed between clock domains in gray
+/// code through two-flop synchronizers, so `deq` observes a value two or
+/// three read clock edges after it is accepted on `enq`.
+pub struct AsyncFifo<T: Copy + std::fmt::Debug + 'static, const DEPTH: usize> {
+    pub enq: Decoupled<T>,
+    pub deq: Decoupled<T>,
+    wdomain: DomainId,
+    rdomain: DomainId,
+    data: [RegisterId<T>; DEPTH],
+    /// Write pointer (modulo `2 * DEPTH`)
+    wptr: RegisterId<usize>,
+    /// Read pointer (modulo `2 * DEPTH`)
+    rptr: RegisterId<usize>,
+    /// Gray-coded read pointer synchronized into the write domain
+    rptr_sync: [RegisterId<usize>; 2],
+    /// Gray-coded write pointer synchronized into the read domain
+    wptr_sync: [RegisterId<usize>; 2],
+    instance: InstanceId,
+}
+impl <T, const DEPTH: usize> AsyncFifo<T, DEPTH>
+    where T: Copy + Default + std::fmt::Debug + 'static
+{
+    /// Create a FIFO written in the `wdomain` clock domain and read in the
+    /// `rdomain` clock domain.
+    pub fn new(state: &mut EngineState, wdomain: DomainId, rdomain: DomainId)
+        -> Self
+    {
+        assert!(DEPTH.is_power_of_two(), "AsyncFifo depth must be a power of two");
+        let instance = state.alloc_instance::<Self>();
+        let regs = &mut state.registers;
+        Self {
+            enq: Decoupled::new(&mut state.wires),
+            deq: Decoupled::new(&mut state.wires),
+            wdomain,
+            rdomain,
+            data: std::array::from_fn(|_| regs.alloc_in(wdomain, T::default())),
+            wptr: regs.alloc_in(wdomain, 0),
+            rptr: regs.alloc_in(rdomain, 0),
+            rptr_sync: std::array::from_fn(|_| regs.alloc_in(wdomain, 0)),
+            wptr_sync: std::array::from_fn(|_| regs.alloc_in(rdomain, 0)),
+            instance,
+        }
+    }
+}
+impl <T, const DEPTH: usize> ModuleLike for AsyncFifo<T, DEPTH>
+    where T: Copy + Default + std::fmt::Debug + 'static
+{
+    fn new_instance(state: &mut EngineState) -> Self {
+        Self::new(state, DomainId::DEFAULT, DomainId::DEFAULT)
+    }
+    fn instance(&self) -> Option<InstanceId> { Some(self.instance) }
+    async fn run(&self) {
+        let wptr = self.wptr.sample().await;
+        let rptr = self.rptr.sample().await;
+        let rptr_sync = [self.rptr_sync[0].sample().await, self.rptr_sync[1].sample().await];
+        let wptr_sync = [self.wptr_sync[0].sample().await, self.wptr_sync[1].sample().await];
+
+        // Write domain
+        let used = (wptr + 2 * DEPTH - from_gray(rptr_sync[1])) % (2 * DEPTH);
+        let enq_ready = used < DEPTH && self.wdomain.is_edge().await;
+        self.enq.ready.drive(enq_ready).await;
+
+        // Read domain
+        let empty = from_gray(wptr_sync[1]) == rptr;
+        let deq_valid = !empty && self.rdomain.is_edge().await;
+        self.deq.valid.drive(deq_valid).await;
+        if deq_valid {
+            self.deq.data.drive(self.data[rptr % DEPTH].sample().await).await;
+        }
+
+        self.rptr_sync[1].drive(rptr_sync[0]).await;
+        self.rptr_sync[0].drive(to_gray(rptr)).await;
+        self.wptr_sync[1].drive(wptr_sync[0]).await;
+        self.wptr_sync[0].drive(to_gray(wptr)).await;
+
+        if enq_ready && self.enq.valid.sample().await {
+            self.data[wptr % DEPTH].drive(self.enq.data.sample().await).await;
+            self.wptr.drive((wptr + 1) % (2 * DEPTH)).await;
+        }
+        if deq_valid && self.deq.ready.sample().await {
+            self.rptr.drive((rptr + 1) % (2 * DEPTH)).await;
+        }
+    }
+}
+
+/// Flags registers and wires from one clock domain which are sampled
+/// directly by logic in another domain.
+///
+/// Only tasks scheduled with
+/// [`Engine::schedule_in`](crate::engine::Engine::schedule_in) or
+/// [`Engine::schedule_module_in`](crate::engine::Engine::schedule_module_in)
+/// are checked. Wires belong to the clock domain of the task driving them.
+#[derive(Default)]
+pub struct CdcChecker {
+    /// Crossings observed so far
+    pub violations: Vec<DomainCrossing>,
+}
+impl CdcChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+impl Checker for CdcChecker {
+    fn check(&mut self, _cycle: usize, state: &EngineState) {
+        self.violations.extend(state.crossings.borrow().iter().cloned());
+    }
+}
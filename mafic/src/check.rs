@@ -0,0 +1,91 @@
+//! Checks performed on the simulated state at the end of each cycle.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::engine::EngineState;
+use crate::logic::FourState;
+use crate::wire::WireId;
+use crate::register::RegisterId;
+
+/// Trait implemented on types that inspect the simulated state at the end
+/// of each cycle.
+///
+/// Checkers are attached with
+/// [`Engine::add_checker`](crate::engine::Engine::add_checker), and are
+/// called after all tasks have completed (before wires are reset and
+/// registers are updated).
+pub trait Checker {
+    fn check(&mut self, cycle: usize, state: &EngineState);
+}
+
+/// Allows the user to keep a handle to a checker owned by an engine.
+impl <C: Checker> Checker for Rc<RefCell<C>> {
+    fn check(&mut self, cycle: usize, state: &EngineState) {
+        self.borrow_mut().check(cycle, state)
+    }
+}
+
+/// An unknown value observed by [`XChecker`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct XViolation {
+    /// The cycle in which the value was observed
+    pub cycle: usize,
+    /// The name of the wire/register
+    pub name: String,
+    /// The value (formatted with [`std::fmt::Debug`])
+    pub value: String,
+}
+
+/// Returns the formatted value of a signal when it contains unknown bits.
+type XProbe = Box<dyn Fn(&EngineState) -> Option<String>>;
+
+/// Flags unknown (X/Z) values on designated wires and registers (ie.
+/// outputs and control signals) at the end of each cycle.
+#[derive(Default)]
+pub struct XChecker {
+    /// Designated signals
+    watched: Vec<(String, XProbe)>,
+
+    /// Unknown values observed so far
+    pub violations: Vec<XViolation>,
+}
+impl XChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Flag unknown values on a wire. Undriven wires are ignored.
+    pub fn watch_wire<T>(&mut self, name: impl Into<String>, wire: WireId<T>)
+        where T: FourState + std::fmt::Debug + 'static
+    {
+        self.watched.push((name.into(), Box::new(move |state| {
+            state.wires.peek_wire(wire)
+                .filter(|v| v.is_unknown())
+                .map(|v| format!("{:?}", v))
+        })));
+    }
+
+    /// Flag unknown values in a register.
+    pub fn watch_register<T>(&mut self, name: impl Into<String>,
+        reg: RegisterId<T>)
+        where T: FourState + std::fmt::Debug + 'static
+    {
+        self.watched.push((name.into(), Box::new(move |state| {
+            Some(state.registers.peek_register(reg))
+                .filter(|v| v.is_unknown())
+                .map(|v| format!("{:?}", v))
+        })));
+    }
+}
+impl Checker for XChecker {
+    fn check(&mut self, cycle: usize, state: &EngineState) {
+        for (name, f) in &self.watched {
+            if let Some(value) = f(state) {
+                self.violations.push(XViolation {
+                    cycle, name: name.clone(), value
+                });
+            }
+        }
+    }
+}
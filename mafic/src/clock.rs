@@ -0,0 +1,112 @@
+//! Clock domains.
+//!
+//! Each step of an [`Engine`](crate::engine::Engine) is a single "tick" of
+//! the base clock. A [`ClockDomain`] has a rising edge every `period` ticks
+//! (starting at tick `phase`), and registers belonging to a domain are only
+//! updated on ticks where the domain has an edge.
+//!
+//! Tasks are still polled on every tick. Values driven on a register during
+//! a tick without an edge are discarded, so logic in a slower domain is
+//! effectively evaluated on the ticks where its clock has an edge.
+//!
+//! Registers commit on the rising edge unless they are tagged with another
+//! [`ClockEdge`]. When any register commits on the falling edge, each tick
+//! is split into a [`ClockPhase::High`] and a [`ClockPhase::Low`] half
+//! (see [`Engine::try_step`](crate::engine::Engine::try_step)). This 
+//! applies to the whole engine: modules added with
+//! [`Engine::add_module`](crate::engine::Engine::add_module) run twice per
+//! tick, even if they only use rising-edge registers.
+
+use std::future::{ Future, poll_fn };
+use std::sync::{ Arc, Mutex };
+use std::task::Poll;
+
+use crate::engine::EngineState;
+
+/// Identifier for a [`ClockDomain`] tracked by [`EngineState`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct DomainId(pub(crate) usize);
+impl DomainId {
+    /// The base clock, which has an edge on every tick
+    pub const DEFAULT: Self = Self(0);
+
+    pub fn id(&self) -> usize { self.0 }
+
+    /// Returns 'true' if this domain has an edge at the end of the current
+    /// tick.
+    pub fn is_edge(self) -> impl Future<Output = bool> {
+        poll_fn(move |ctx| {
+            let state: &mut Arc<Mutex<EngineState>> = ctx.ext().downcast_mut().unwrap();
+            Poll::Ready(state.lock().unwrap().is_edge(self))
+        })
+    }
+}
+
+/// A signal from one clock domain sampled by logic in another domain.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DomainCrossing {
+    /// The cycle (tick) in which the signal was sampled
+    pub cycle: usize,
+    /// The name of the register/wire
+    pub signal: String,
+    /// The clock domain of the signal
+    pub from: DomainId,
+    /// The clock domain of the logic sampling the signal
+    pub to: DomainId,
+}
+
+/// A clock with a rising edge every `period` ticks of the base clock.
+#[derive(Clone, Debug)]
+pub struct ClockDomain {
+    pub name: String,
+    /// Number of ticks between edges
+    pub period: usize,
+    /// The first tick with an edge
+    pub phase: usize,
+}
+impl ClockDomain {
+    /// Returns 'true' if this clock has an edge at the end of the given tick.
+    pub fn is_edge(&self, tick: usize) -> bool {
+        tick >= self.phase && (tick - self.phase).is_multiple_of(self.period)
+    }
+}
+
+/// The clock edge(s) on which a register commits.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ClockEdge {
+    /// The rising edge, at the end of a tick
+    #[default]
+    Pos,
+    /// The falling edge, between the two halves of a tick
+    Neg,
+    /// Both edges
+    Both,
+}
+impl ClockEdge {
+    /// Returns 'true' if registers with this edge commit on the falling edge.
+    pub fn is_neg(self) -> bool {
+        matches!(self, Self::Neg | Self::Both)
+    }
+    /// Returns 'true' if registers with this edge commit on the rising edge.
+    pub fn is_pos(self) -> bool {
+        matches!(self, Self::Pos | Self::Both)
+    }
+}
+
+/// The half of a tick currently being simulated.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ClockPhase {
+    /// After the rising edge (the whole tick, without falling-edge registers)
+    #[default]
+    High,
+    /// After the falling edge
+    Low,
+}
+
+/// Return the current half of the tick.
+pub fn clock_phase() -> impl Future<Output = ClockPhase> {
+    poll_fn(|ctx| {
+        let state: &mut Arc<Mutex<EngineState>> = ctx.ext().downcast_mut().unwrap();
+        Poll::Ready(state.lock().unwrap().phase)
+    })
+}
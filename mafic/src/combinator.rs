@@ -0,0 +1,235 @@
+//! Combinators for awaiting several futures concurrently.
+//!
+//! Awaiting wires one-at-a-time in a fixed order is fine for small modules,
+//! but a process that *needs* to make progress on whichever wire is driven
+//! first can use these instead:
+//!
+//! - [`join`] and [`join!`](crate::join) wait for all futures to complete
+//! - [`join_all`] waits for a list of futures to complete
+//! - [`Join::join`] waits for a tuple or array of futures to complete
+//! - [`select`] and [`select!`](crate::select) wait for the first future
+//!   to complete
+//! - [`sample_all`] samples a tuple of wires concurrently
+//!
+//! All of these poll each of the inner futures in order. Futures waiting on
+//! wires are blocked with [`EngineState::wait_on_wire`], so a task using
+//! these combinators is woken up when *any* of the wires is driven.
+//!
+//! [`EngineState::wait_on_wire`]: crate::engine::EngineState::wait_on_wire
+
+use std::future::{ Future, poll_fn };
+use std::pin::{ Pin, pin };
+use std::task::{ Context, Poll };
+
+use crate::wire::WireId;
+
+/// A future which holds onto its output after completion.
+enum MaybeDone<F: Future> {
+    Pending(F),
+    Done(F::Output),
+    Taken,
+}
+impl <F: Future> MaybeDone<F> {
+    /// Poll the inner future, returning 'true' if it has completed.
+    fn poll_done(self: Pin<&mut Self>, cx: &mut Context<'_>) -> bool {
+        // SAFETY: The inner future is never moved out of this pinned value
+        let this = unsafe { self.get_unchecked_mut() };
+        match this {
+            Self::Pending(f) => {
+                let f = unsafe { Pin::new_unchecked(f) };
+                if let Poll::Ready(res) = f.poll(cx) {
+                    *this = Self::Done(res);
+                    true
+                } else {
+                    false
+                }
+            },
+            Self::Done(_) => true,
+            Self::Taken => panic!("MaybeDone polled after output was taken"),
+        }
+    }
+
+    /// Take the output of the completed future.
+    fn take(self: Pin<&mut Self>) -> F::Output {
+        // SAFETY: The output is not structurally pinned
+        let this = unsafe { self.get_unchecked_mut() };
+        match std::mem::replace(this, Self::Taken) {
+            Self::Done(res) => res,
+            _ => panic!("MaybeDone has no output"),
+        }
+    }
+}
+
+/// Trait implemented on tuples of futures which can be awaited together.
+pub trait Join {
+    type Output;
+
+    /// Wait for all futures to complete.
+    async fn join(self) -> Self::Output;
+}
+
+/// Trait implemented on tuples of wires which can be sampled together.
+pub trait SampleAll {
+    type Output;
+
+    /// Wait for all wires to be driven, and return their values.
+    async fn sample_all(self) -> Self::Output;
+}
+
+macro_rules! impl_join_tuple {
+    ($($F:ident $f:ident),+) => {
+        impl <$($F: Future),+> Join for ($($F,)+) {
+            type Output = ($($F::Output,)+);
+            async fn join(self) -> Self::Output {
+                let ($($f,)+) = self;
+                $( let mut $f = pin!(MaybeDone::Pending($f)); )+
+                poll_fn(|cx| {
+                    let mut done = true;
+                    $( done &= $f.as_mut().poll_done(cx); )+
+                    if done { Poll::Ready(()) } else { Poll::Pending }
+                }).await;
+                ($($f.as_mut().take(),)+)
+            }
+        }
+
+        impl <$($F: Copy + std::fmt::Debug + 'static),+> SampleAll
+            for ($(WireId<$F>,)+)
+        {
+            type Output = ($($F,)+);
+            async fn sample_all(self) -> Self::Output {
+                let ($($f,)+) = self;
+                ($($f.sample(),)+).join().await
+            }
+        }
+    };
+}
+impl_join_tuple!(A a);
+impl_join_tuple!(A a, B b);
+impl_join_tuple!(A a, B b, C c);
+impl_join_tuple!(A a, B b, C c, D d);
+impl_join_tuple!(A a, B b, C c, D d, E e);
+impl_join_tuple!(A a, B b, C c, D d, E e, F f);
+impl_join_tuple!(A a, B b, C c, D d, E e, F f, G g);
+impl_join_tuple!(A a, B b, C c, D d, E e, F f, G g, H h);
+
+impl <F: Future, const N: usize> Join for [F; N] {
+    type Output = [F::Output; N];
+    async fn join(self) -> Self::Output {
+        let mut futs = self.map(|f| Box::pin(MaybeDone::Pending(f)));
+        poll_fn(|cx| {
+            let mut done = true;
+            for f in futs.iter_mut() {
+                done &= f.as_mut().poll_done(cx);
+            }
+            if done { Poll::Ready(()) } else { Poll::Pending }
+        }).await;
+        futs.map(|mut f| f.as_mut().take())
+    }
+}
+
+/// Wait for both futures to complete.
+pub async fn join<A: Future, B: Future>(a: A, b: B) -> (A::Output, B::Output) {
+    (a, b).join().await
+}
+
+/// Wait for all futures in a list to complete.
+pub async fn join_all<F: Future>(futs: impl IntoIterator<Item = F>) 
+    -> Vec<F::Output>
+{
+    let mut futs: Vec<_> = futs.into_iter()
+        .map(|f| Box::pin(MaybeDone::Pending(f)))
+        .collect();
+    poll_fn(|cx| {
+        let mut done = true;
+        for f in futs.iter_mut() {
+            done &= f.as_mut().poll_done(cx);
+        }
+        if done { Poll::Ready(()) } else { Poll::Pending }
+    }).await;
+    futs.iter_mut().map(|f| f.as_mut().take()).collect()
+}
+
+/// Sample a tuple of wires concurrently (ie. `sample_all((w1, w2, w3))`).
+pub async fn sample_all<S: SampleAll>(wires: S) -> S::Output {
+    wires.sample_all().await
+}
+
+/// The output of [`select`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Either<A, B> {
+    Left(A),
+    Right(B),
+}
+
+/// Wait for the first of two futures to complete.
+///
+/// When both futures are able to complete, `a` is preferred.
+/// The other future is dropped.
+pub async fn select<A: Future, B: Future>(a: A, b: B)
+    -> Either<A::Output, B::Output>
+{
+    let mut a = pin!(a);
+    let mut b = pin!(b);
+    poll_fn(|cx| {
+        if let Poll::Ready(res) = a.as_mut().poll(cx) {
+            return Poll::Ready(Either::Left(res));
+        }
+        if let Poll::Ready(res) = b.as_mut().poll(cx) {
+            return Poll::Ready(Either::Right(res));
+        }
+        Poll::Pending
+    }).await
+}
+
+/// Wait for all futures to complete, evaluating to a tuple of their outputs.
+///
+/// This can only be used inside `async` code.
+///
+/// ```ignore
+/// let (x, y) = join!(self.x.sample(), self.y.sample());
+/// ```
+#[macro_export]
+macro_rules! join {
+    ($($fut:expr),+ $(,)?) => {
+        $crate::combinator::Join::join(($($fut,)+)).await
+    };
+}
+
+/// Wait for the first of several futures to complete, and evaluate the
+/// expression associated with it.
+///
+/// This can only be used inside `async` code. When several futures are able
+/// to complete, the first one is preferred.
+///
+/// ```ignore
+/// select! {
+///     x = self.a.valid.sample() => { ... },
+///     y = self.b.valid.sample() => { ... },
+/// }
+/// ```
+#[macro_export]
+macro_rules! select {
+    // Nest the futures (ie. `select(a, select(b, c))`)
+    (@fut $fut:expr) => { async { $fut.await } };
+    (@fut $fut:expr, $($rest:expr),+) => {
+        $crate::combinator::select($fut, $crate::select!(@fut $($rest),+))
+    };
+
+    // Unwrap the nested results
+    (@arm $res:ident; $pat:pat => $body:expr) => {
+        match $res { $pat => $body }
+    };
+    (@arm $res:ident; $pat:pat => $body:expr, $($rest:tt)+) => {
+        match $res {
+            $crate::combinator::Either::Left($pat) => $body,
+            $crate::combinator::Either::Right(res) => {
+                $crate::select!(@arm res; $($rest)+)
+            },
+        }
+    };
+
+    ($($pat:pat = $fut:expr => $body:expr),+ $(,)?) => {{
+        let res = $crate::select!(@fut $($fut),+).await;
+        $crate::select!(@arm res; $($pat => $body),+)
+    }};
+}
@@ -0,0 +1,535 @@
+//! Functional coverage.
+//!
+//! A [`Coverpoint`] samples a wire or register at the end of each cycle,
+//! and counts the number of samples falling into each of its bins. A cross
+//! counts the cycles in which each combination of bins from several
+//! coverpoints was hit together.
+//!
+//! ```ignore
+//! let mut cov = Coverage::new();
+//! let count = cov.add(Coverpoint::wire("fifo.count", fifo.count)
+//!     .value("empty", 0)
+//!     .range("partial", 1..=DEPTH-1)
+//!     .value("full", DEPTH));
+//! let op = cov.add(Coverpoint::register("op", op).values([Op::Add, Op::Sub]));
+//! cov.cross("count_x_op", &[count, op]);
+//! let cov = Rc::new(RefCell::new(cov));
+//! e.add_checker(cov.clone());
+//! ...
+//! let report = cov.borrow().report();
+//! println!("{}", report);
+//! std::fs::write("coverage.json", report.to_json())?;
+//! ```
+//!
+//! Reports from several runs can be combined with [`CoverageReport::merge`]
+//! (ie. after reading them back with [`CoverageReport::from_json`]).
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::check::Checker;
+use crate::engine::EngineState;
+use crate::register::RegisterId;
+use crate::wire::WireId;
+
+/// Samples the value of a signal.
+type Probe<T> = Box<dyn Fn(&EngineState) -> Option<T>>;
+
+/// Returns 'true' for values that fall into a bin.
+type BinFn<T> = Box<dyn Fn(&T) -> bool>;
+
+/// A set of bins counting the values sampled from a signal.
+///
+/// Values which do not fall into any bin are ignored. A value may fall
+/// into more than one bin.
+pub struct Coverpoint<T: 'static> {
+    name: String,
+    probe: Probe<T>,
+    /// Only sample the signal when this wire is high (if any)
+    enable: Option<WireId<bool>>,
+    bins: Vec<(String, BinFn<T>)>,
+    hits: Vec<usize>,
+}
+impl <T: Copy + fmt::Debug + 'static> Coverpoint<T> {
+    /// Sample a value computed from the simulated state. Nothing is sampled
+    /// in cycles where `probe` returns `None`.
+    pub fn new(name: impl Into<String>,
+        probe: impl Fn(&EngineState) -> Option<T> + 'static) -> Self
+    {
+        Self {
+            name: name.into(),
+            probe: Box::new(probe),
+            enable: None,
+            bins: Vec::new(),
+            hits: Vec::new(),
+        }
+    }
+
+    /// Sample a wire. Nothing is sampled in cycles where the wire is not
+    /// driven.
+    pub fn wire(name: impl Into<String>, wire: WireId<T>) -> Self {
+        Self::new(name, move |state| state.wires.peek_wire(wire))
+    }
+
+    /// Sample a register.
+    pub fn register(name: impl Into<String>, reg: RegisterId<T>) -> Self {
+        Self::new(name, move |state| Some(state.registers.peek_register(reg)))
+    }
+
+    /// Only sample in cycles where `enable` is high (like `iff`).
+    pub fn iff(mut self, enable: WireId<bool>) -> Self {
+        self.enable = Some(enable);
+        self
+    }
+
+    /// Add a bin for values satisfying a predicate.
+    pub fn bin(mut self, name: impl Into<String>,
+        f: impl Fn(&T) -> bool + 'static) -> Self
+    {
+        self.bins.push((name.into(), Box::new(f)));
+        self.hits.push(0);
+        self
+    }
+
+    /// Add a bin for a single value.
+    pub fn value(self, name: impl Into<String>, value: T) -> Self
+        where T: PartialEq
+    {
+        self.bin(name, move |v| *v == value)
+    }
+
+    /// Add a bin for a range of values.
+    pub fn range(self, name: impl Into<String>,
+        range: std::ops::RangeInclusive<T>) -> Self
+        where T: PartialOrd
+    {
+        self.bin(name, move |v| range.contains(v))
+    }
+
+    /// Add a bin for each value (named with [`std::fmt::Debug`]).
+    pub fn values(self, values: impl IntoIterator<Item = T>) -> Self
+        where T: PartialEq
+    {
+        values.into_iter().fold(self, |cp, v| cp.value(format!("{:?}", v), v))
+    }
+}
+
+/// A coverpoint with the value type erased.
+trait Sampled {
+    fn name(&self) -> &str;
+    fn bin_names(&self) -> Vec<String>;
+    fn hits(&self) -> &[usize];
+    /// Sample the signal, returning the index of each bin which was hit.
+    fn sample(&mut self, state: &EngineState) -> Vec<usize>;
+}
+impl <T: Copy + fmt::Debug + 'static> Sampled for Coverpoint<T> {
+    fn name(&self) -> &str { &self.name }
+    fn bin_names(&self) -> Vec<String> {
+        self.bins.iter().map(|(name, _)| name.clone()).collect()
+    }
+    fn hits(&self) -> &[usize] { &self.hits }
+    fn sample(&mut self, state: &EngineState) -> Vec<usize> {
+        if let Some(enable) = self.enable
+            && state.wires.peek_wire(enable) != Some(true)
+        {
+            return Vec::new();
+        }
+        let Some(value) = (self.probe)(state) else { return Vec::new() };
+        let hit: Vec<usize> = self.bins.iter().enumerate()
+            .filter(|(_, (_, f))| f(&value))
+            .map(|(idx, _)| idx)
+            .collect();
+        for idx in &hit {
+            self.hits[*idx] += 1;
+        }
+        hit
+    }
+}
+
+/// Identifier for a [`Coverpoint`] added to [`Coverage`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PointId(usize);
+
+/// Counts the combinations of bins hit in the same cycle.
+struct Cross {
+    name: String,
+    points: Vec<PointId>,
+    hits: HashMap<Vec<usize>, usize>,
+}
+
+/// Collects functional coverage at the end of each cycle.
+#[derive(Default)]
+pub struct Coverage {
+    points: Vec<Box<dyn Sampled>>,
+    crosses: Vec<Cross>,
+}
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a coverpoint.
+    pub fn add<T: Copy + fmt::Debug + 'static>(&mut self, point: Coverpoint<T>)
+        -> PointId
+    {
+        self.points.push(Box::new(point));
+        PointId(self.points.len() - 1)
+    }
+
+    /// Add a cross between several coverpoints.
+    pub fn cross(&mut self, name: impl Into<String>, points: &[PointId]) {
+        self.crosses.push(Cross {
+            name: name.into(), points: points.to_vec(), hits: HashMap::new()
+        });
+    }
+
+    /// Return the results collected so far.
+    pub fn report(&self) -> CoverageReport {
+        let coverpoints = self.points.iter().map(|p| {
+            GroupReport {
+                name: p.name().to_string(),
+                bins: p.bin_names().into_iter().zip(p.hits().iter().copied())
+                    .collect(),
+            }
+        }).collect();
+
+        let crosses = self.crosses.iter().map(|c| {
+            // Enumerate every combination of bins
+            let mut combos: Vec<(Vec<usize>, Vec<String>)> = vec![(Vec::new(), Vec::new())];
+            for id in &c.points {
+                let names = self.points[id.0].bin_names();
+                combos = combos.into_iter().flat_map(|(idx, name)| {
+                    names.iter().enumerate().map(move |(i, n)| {
+                        let mut idx = idx.clone();
+                        let mut name = name.clone();
+                        idx.push(i);
+                        name.push(n.clone());
+                        (idx, name)
+                    })
+                }).collect();
+            }
+            GroupReport {
+                name: c.name.clone(),
+                bins: combos.into_iter().map(|(idx, name)| {
+                    (name.join("/"), c.hits.get(&idx).copied().unwrap_or(0))
+                }).collect(),
+            }
+        }).collect();
+
+        CoverageReport { coverpoints, crosses }
+    }
+}
+impl Checker for Coverage {
+    fn check(&mut self, _cycle: usize, state: &EngineState) {
+        let hit: Vec<Vec<usize>> = self.points.iter_mut()
+            .map(|p| p.sample(state))
+            .collect();
+        for c in self.crosses.iter_mut() {
+            let mut combos: Vec<Vec<usize>> = vec![Vec::new()];
+            for id in &c.points {
+                combos = combos.into_iter().flat_map(|combo| {
+                    hit[id.0].iter().map(move |i| {
+                        let mut combo = combo.clone();
+                        combo.push(*i);
+                        combo
+                    })
+                }).collect();
+            }
+            for combo in combos {
+                *c.hits.entry(combo).or_insert(0) += 1;
+            }
+        }
+    }
+}
+
+/// The number of hits in each bin of a coverpoint or cross.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GroupReport {
+    pub name: String,
+    pub bins: Vec<(String, usize)>,
+}
+impl GroupReport {
+    /// Return the number of bins which were hit.
+    pub fn num_hit(&self) -> usize {
+        self.bins.iter().filter(|(_, hits)| *hits > 0).count()
+    }
+
+    /// Add the hits from another report. Bins are matched by name.
+    fn merge(&mut self, other: &GroupReport) {
+        for (name, hits) in &other.bins {
+            match self.bins.iter_mut().find(|(n, _)| n == name) {
+                Some((_, h)) => *h += hits,
+                None => self.bins.push((name.clone(), *hits)),
+            }
+        }
+    }
+
+    fn fmt_group(&self, f: &mut fmt::Formatter<'_>, kind: &str) -> fmt::Result {
+        writeln!(f, "  {} {} ({}/{} bins, {:.1}%)", kind, self.name,
+            self.num_hit(), self.bins.len(), percent(self.num_hit(), self.bins.len()))?;
+        for (name, hits) in &self.bins {
+            if *hits == 0 {
+                writeln!(f, "    {}: 0 (missed)", name)?;
+            } else {
+                writeln!(f, "    {}: {}", name, hits)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn percent(hit: usize, total: usize) -> f64 {
+    if total == 0 { 100.0 } else { 100.0 * hit as f64 / total as f64 }
+}
+
+/// Coverage results.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct CoverageReport {
+    pub coverpoints: Vec<GroupReport>,
+    pub crosses: Vec<GroupReport>,
+}
+impl CoverageReport {
+    fn groups(&self) -> impl Iterator<Item = &GroupReport> {
+        self.coverpoints.iter().chain(self.crosses.iter())
+    }
+
+    /// Return the percentage of bins which were hit.
+    pub fn percent(&self) -> f64 {
+        let hit = self.groups().map(|g| g.num_hit()).sum();
+        let total = self.groups().map(|g| g.bins.len()).sum();
+        percent(hit, total)
+    }
+
+    /// Return the coverpoint or cross with the given name.
+    pub fn group(&self, name: &str) -> Option<&GroupReport> {
+        self.groups().find(|g| g.name == name)
+    }
+
+    /// Add the hits from another report (ie. from another run).
+    /// Coverpoints, crosses, and bins are matched by name.
+    pub fn merge(&mut self, other: &CoverageReport) {
+        fn merge_groups(dst: &mut Vec<GroupReport>, src: &[GroupReport]) {
+            for g in src {
+                match dst.iter_mut().find(|d| d.name == g.name) {
+                    Some(d) => d.merge(g),
+                    None => dst.push(g.clone()),
+                }
+            }
+        }
+        merge_groups(&mut self.coverpoints, &other.coverpoints);
+        merge_groups(&mut self.crosses, &other.crosses);
+    }
+
+    /// Format this report as JSON.
+    pub fn to_json(&self) -> String {
+        fn groups_json(groups: &[GroupReport]) -> String {
+            let groups: Vec<String> = groups.iter().map(|g| {
+                let bins: Vec<String> = g.bins.iter().map(|(name, hits)| {
+                    format!("{{\"name\":{},\"hits\":{}}}", json_string(name), hits)
+                }).collect();
+                format!("{{\"name\":{},\"bins\":[{}]}}", json_string(&g.name), bins.join(","))
+            }).collect();
+            format!("[{}]", groups.join(","))
+        }
+        format!("{{\"coverpoints\":{},\"crosses\":{}}}",
+            groups_json(&self.coverpoints), groups_json(&self.crosses))
+    }
+
+    /// Read a report produced by [`CoverageReport::to_json`].
+    pub fn from_json(s: &str) -> Result<Self, JsonError> {
+        let mut p = JsonParser { s: s.as_bytes(), pos: 0 };
+        let mut report = Self::default();
+        p.object(|p, key| {
+            let groups = match key.as_str() {
+                "coverpoints" => &mut report.coverpoints,
+                "crosses" => &mut report.crosses,
+                _ => return Err(p.err("unknown key")),
+            };
+            p.array(|p| {
+                let mut group = GroupReport { name: String::new(), bins: Vec::new() };
+                p.object(|p, key| match key.as_str() {
+                    "name" => { group.name = p.string()?; Ok(()) },
+                    "bins" => p.array(|p| {
+                        let mut bin = (String::new(), 0);
+                        p.object(|p, key| match key.as_str() {
+                            "name" => { bin.0 = p.string()?; Ok(()) },
+                            "hits" => { bin.1 = p.number()?; Ok(()) },
+                            _ => Err(p.err("unknown key")),
+                        })?;
+                        group.bins.push(bin);
+                        Ok(())
+                    }),
+                    _ => Err(p.err("unknown key")),
+                })?;
+                groups.push(group);
+                Ok(())
+            })
+        })?;
+        p.ws();
+        if p.pos != p.s.len() {
+            return Err(p.err("trailing characters"));
+        }
+        Ok(report)
+    }
+}
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "coverage report ({:.1}%)", self.percent())?;
+        for g in &self.coverpoints {
+            g.fmt_group(f, "coverpoint")?;
+        }
+        for g in &self.crosses {
+            g.fmt_group(f, "cross")?;
+        }
+        Ok(())
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut res = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            c if (c as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+/// Error returned when reading a malformed coverage report.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct JsonError {
+    /// The offset of the error (in bytes)
+    pub pos: usize,
+    pub msg: &'static str,
+}
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid coverage report at offset {}: {}", self.pos, self.msg)
+    }
+}
+impl std::error::Error for JsonError {}
+
+/// Reads the subset of JSON produced by [`CoverageReport::to_json`].
+struct JsonParser<'s> {
+    s: &'s [u8],
+    pos: usize,
+}
+impl JsonParser<'_> {
+    fn err(&self, msg: &'static str) -> JsonError {
+        JsonError { pos: self.pos, msg }
+    }
+
+    fn ws(&mut self) {
+        while self.s.get(self.pos).is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consume the character `c` (after any whitespace).
+    fn expect(&mut self, c: u8) -> Result<(), JsonError> {
+        self.ws();
+        if self.s.get(self.pos) != Some(&c) {
+            return Err(self.err("unexpected character"));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// Consume the character `c` if it is next.
+    fn eat(&mut self, c: u8) -> bool {
+        self.ws();
+        let res = self.s.get(self.pos) == Some(&c);
+        if res {
+            self.pos += 1;
+        }
+        res
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+        loop {
+            let Some(&c) = self.s.get(self.pos) else {
+                return Err(self.err("unterminated string"));
+            };
+            self.pos += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let Some(&e) = self.s.get(self.pos) else {
+                        return Err(self.err("unterminated string"));
+                    };
+                    self.pos += 1;
+                    match e {
+                        b'u' => {
+                            let hex = self.s.get(self.pos..self.pos + 4)
+                                .and_then(|h| std::str::from_utf8(h).ok())
+                                .and_then(|h| u32::from_str_radix(h, 16).ok())
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.err("invalid escape"))?;
+                            self.pos += 4;
+                            let mut buf = [0; 4];
+                            bytes.extend(hex.encode_utf8(&mut buf).as_bytes());
+                        },
+                        b'n' => bytes.push(b'\n'),
+                        b't' => bytes.push(b'\t'),
+                        b'r' => bytes.push(b'\r'),
+                        b'"' | b'\\' | b'/' => bytes.push(e),
+                        _ => return Err(self.err("invalid escape")),
+                    }
+                },
+                c => bytes.push(c),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| self.err("invalid utf-8"))
+    }
+
+    fn number(&mut self) -> Result<usize, JsonError> {
+        self.ws();
+        let start = self.pos;
+        while self.s.get(self.pos).is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.s[start..self.pos]).ok()
+            .and_then(|n| n.parse().ok())
+            .ok_or_else(|| self.err("invalid number"))
+    }
+
+    /// Read an object, calling `f` to read the value for each key.
+    fn object(&mut self, mut f: impl FnMut(&mut Self, String) -> Result<(), JsonError>)
+        -> Result<(), JsonError>
+    {
+        self.expect(b'{')?;
+        if self.eat(b'}') {
+            return Ok(());
+        }
+        loop {
+            let key = self.string()?;
+            self.expect(b':')?;
+            f(self, key)?;
+            if !self.eat(b',') {
+                return self.expect(b'}');
+            }
+        }
+    }
+
+    /// Read an array, calling `f` to read each element.
+    fn array(&mut self, mut f: impl FnMut(&mut Self) -> Result<(), JsonError>)
+        -> Result<(), JsonError>
+    {
+        self.expect(b'[')?;
+        if self.eat(b']') {
+            return Ok(());
+        }
+        loop {
+            f(self)?;
+            if !self.eat(b',') {
+                return self.expect(b']');
+            }
+        }
+    }
+}
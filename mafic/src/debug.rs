@@ -0,0 +1,373 @@
+//! An interactive debugger for simulations.
+//!
+//! A [`Debugger`] reads commands from a terminal or a local TCP socket, and
+//! controls an [`Engine`] between cycles:
+//!
+//! ```ignore
+//! e.run_until(|s| s.cycle == 40_000).unwrap();
+//! Debugger::new(&mut e).stdio().unwrap();
+//! ```
+//!
+//! Signals are referred to by their human-readable (hierarchical) names,
+//! and signals without a name are `wire{id}`, `reg{id}`, or `latch{id}` (as
+//! in [`StateTrace`]). Registers and latches show their current value, and
+//! wires show the value driven during the last simulated cycle.
+//!
+//! Type `help` for a list of commands.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::io::{ self, BufRead, BufReader, Write };
+use std::net::TcpListener;
+use std::rc::Rc;
+
+use crate::engine::Engine;
+use crate::latch::LatchState;
+use crate::order::StateTrace;
+use crate::register::RegisterState;
+use crate::wire::WireState;
+
+const HELP: &str = "\
+commands:
+  step [n]                 simulate n cycles (default 1)
+  continue [n]             simulate until a breakpoint holds (at most n cycles)
+  break <signal> == <v>    stop when a signal has a value (also !=)
+  delete <n>               remove a breakpoint
+  print <signal>           print a signal, or all signals under a prefix
+  list [prefix]            list signals
+  force <signal> <v>       pin a wire or register to a value until released
+                           (registers are no longer set just once), or set
+                           the value held by a latch
+  release <signal>         stop forcing a wire or register
+  tasks                    list pending tasks
+  quit                     leave the debugger";
+
+/// A wire, register, or latch (by identifier).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Signal {
+    Wire(usize),
+    Register(usize),
+    Latch(usize),
+}
+impl Signal {
+    fn kind(self) -> &'static str {
+        match self {
+            Self::Wire(_) => "wire",
+            Self::Register(_) => "register",
+            Self::Latch(_) => "latch",
+        }
+    }
+}
+
+/// Stops [`Debugger`] when a signal has (or does not have) some value.
+struct Breakpoint {
+    signal: String,
+    equal: bool,
+    value: String,
+}
+impl std::fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = if self.equal { "==" } else { "!=" };
+        write!(f, "{} {} {}", self.signal, op, self.value)
+    }
+}
+
+/// Interactive front-end for an [`Engine`].
+pub struct Debugger<'e, 'a> {
+    engine: &'e mut Engine<'a>,
+    /// Wire values from the last simulated cycle
+    trace: Rc<RefCell<StateTrace>>,
+    /// Breakpoints (numbered from 1), or `None` after they are deleted
+    breakpoints: Vec<Option<Breakpoint>>,
+}
+impl <'e, 'a> Debugger<'e, 'a> {
+    /// Maximum number of cycles simulated by `continue`
+    const MAX_CYCLES: usize = 1 << 20;
+
+    /// Attach a debugger to an [`Engine`].
+    ///
+    /// This adds a [`StateTrace`] to the engine in order to keep the values
+    /// of wires from the last cycle.
+    pub fn new(engine: &'e mut Engine<'a>) -> Self {
+        let trace = Rc::new(RefCell::new(StateTrace::new()));
+        engine.add_checker(trace.clone());
+        Self { engine, trace, breakpoints: Vec::new() }
+    }
+
+    /// Read commands from stdin until `quit` (or the end of input).
+    pub fn stdio(&mut self) -> io::Result<()> {
+        self.repl(io::stdin().lock(), io::stdout())
+    }
+
+    /// Wait for a single connection on a local TCP socket, and read
+    /// commands from it until `quit` (or the connection is closed).
+    pub fn serve(&mut self, listener: &TcpListener) -> io::Result<()> {
+        eprintln!("mafic: debugger listening on {}", listener.local_addr()?);
+        let (stream, _) = listener.accept()?;
+        self.repl(BufReader::new(stream.try_clone()?), stream)
+    }
+
+    /// Read commands from `input` until `quit` (or the end of input), and
+    /// write the results to `output`.
+    pub fn repl(&mut self, mut input: impl BufRead, mut output: impl Write)
+        -> io::Result<()>
+    {
+        let mut line = String::new();
+        loop {
+            write!(output, "(mafic) ")?;
+            output.flush()?;
+            line.clear();
+            if input.read_line(&mut line)? == 0 || !self.command(&line, &mut output)? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Perform a single command, returning 'false' when the debugger
+    /// should exit.
+    fn command(&mut self, line: &str, out: &mut impl Write) -> io::Result<bool> {
+        let args: Vec<&str> = line.split_whitespace().collect();
+        let Some((cmd, args)) = args.split_first() else { return Ok(true) };
+        let count = |default| match args.first() {
+            Some(n) => n.parse().map_err(|_| format!("invalid count '{}'", n)),
+            None => Ok(default),
+        };
+        let res = match (*cmd, args) {
+            ("help" | "h", _) => Ok(HELP.to_string()),
+            ("quit" | "q", _) => return Ok(false),
+            ("step" | "s", _) => count(1).and_then(|n| self.step(n)),
+            ("continue" | "c", _) => count(Self::MAX_CYCLES).and_then(|n| self.cont(n)),
+            ("break" | "b", [signal, op @ ("==" | "!="), value]) => {
+                self.find(signal).map(|_| {
+                    self.breakpoints.push(Some(Breakpoint {
+                        signal: signal.to_string(),
+                        equal: *op == "==",
+                        value: value.to_string(),
+                    }));
+                    let n = self.breakpoints.len();
+                    format!("breakpoint {}: {}", n, self.breakpoints[n - 1].as_ref().unwrap())
+                })
+            },
+            ("delete" | "d", [n]) => {
+                let bp = n.parse::<usize>().ok()
+                    .and_then(|n| self.breakpoints.get_mut(n.wrapping_sub(1)))
+                    .and_then(|bp| bp.take());
+                match bp {
+                    Some(bp) => Ok(format!("deleted breakpoint {}: {}", n, bp)),
+                    None => Err(format!("no breakpoint {}", n)),
+                }
+            },
+            ("print" | "p", [signal]) => self.print(signal),
+            ("list" | "l", [] | [_]) => {
+                let prefix = args.first().copied().unwrap_or("");
+                Ok(self.signals().into_iter()
+                    .filter(|(name, _)| name.starts_with(prefix))
+                    .map(|(name, sig)| format!("{} {}", sig.kind(), name))
+                    .collect::<Vec<_>>().join("\n"))
+            },
+            ("force" | "f", [signal, value]) => self.force(signal, value),
+            ("release" | "r", [signal]) => self.release(signal),
+            ("tasks" | "t", []) => Ok(self.tasks()),
+            _ => Err(format!("invalid command '{}' (try 'help')", line.trim())),
+        };
+        match res {
+            Ok(s) if s.is_empty() => {},
+            Ok(s) => writeln!(out, "{}", s)?,
+            Err(e) => writeln!(out, "error: {}", e)?,
+        }
+        Ok(true)
+    }
+
+    /// Simulate a cycle, keeping only the last cycle in the trace.
+    fn try_step(&mut self) -> Result<(), String> {
+        let res = self.engine.try_step().map_err(|e| e.to_string());
+        let mut trace = self.trace.borrow_mut();
+        let n = trace.cycles.len();
+        trace.cycles.drain(..n.saturating_sub(1));
+        res
+    }
+
+    fn step(&mut self, n: usize) -> Result<String, String> {
+        for _ in 0..n {
+            self.try_step()?;
+        }
+        Ok(format!("cycle {}", self.engine.cycles()))
+    }
+
+    fn cont(&mut self, n: usize) -> Result<String, String> {
+        for _ in 0..n {
+            self.try_step()?;
+            let hit = self.breakpoints.iter().enumerate()
+                .filter_map(|(i, bp)| Some((i, bp.as_ref()?)))
+                .find(|(_, bp)| {
+                    let value = self.find(&bp.signal).map(|sig| self.value(&bp.signal, sig));
+                    value.is_ok_and(|v| (v == bp.value) == bp.equal)
+                });
+            if let Some((i, bp)) = hit {
+                return Ok(format!("breakpoint {} in cycle {}: {}",
+                    i + 1, self.engine.cycles(), bp));
+            }
+        }
+        Ok(format!("no breakpoint after {} cycles (cycle {})", n, self.engine.cycles()))
+    }
+
+    /// Return the name of every signal.
+    fn signals(&self) -> Vec<(String, Signal)> {
+        let state = self.engine.state().lock().unwrap();
+        let name = |name: Option<&str>, kind, id| {
+            name.map_or_else(|| format!("{}{}", kind, id), |s| s.to_string())
+        };
+        let mut res = Vec::new();
+        for id in state.wires.data.keys().copied() {
+            res.push((name(state.wires.name(id), "wire", id), Signal::Wire(id)));
+        }
+        for id in state.registers.ids() {
+            res.push((name(state.registers.name(id), "reg", id), Signal::Register(id)));
+        }
+        for id in state.latches.ids() {
+            res.push((name(state.latches.name(id), "latch", id), Signal::Latch(id)));
+        }
+        res
+    }
+
+    fn find(&self, name: &str) -> Result<Signal, String> {
+        self.signals().into_iter().find(|(n, _)| n == name).map(|(_, sig)| sig)
+            .ok_or_else(|| format!("unknown signal '{}'", name))
+    }
+
+    /// Format the value of a signal.
+    fn value(&self, name: &str, sig: Signal) -> String {
+        let state = self.engine.state().lock().unwrap();
+        match sig {
+            Signal::Register(id) => state.registers.fmt_register(id),
+            Signal::Latch(id) => state.latches.fmt_latch(id),
+            Signal::Wire(_) => self.trace.borrow().cycles.last()
+                .and_then(|c| c.get(name).cloned())
+                .unwrap_or("undriven".to_string()),
+        }
+    }
+
+    /// Print a signal, or every signal whose name starts with `name.`
+    fn print(&self, name: &str) -> Result<String, String> {
+        let prefix = format!("{}.", name);
+        let lines: Vec<String> = self.signals().into_iter()
+            .filter(|(n, _)| n == name || n.starts_with(&prefix))
+            .map(|(n, sig)| format!("{} = {}", n, self.value(&n, sig)))
+            .collect();
+        if lines.is_empty() {
+            return Err(format!("unknown signal '{}'", name));
+        }
+        Ok(lines.join("\n"))
+    }
+
+    fn force(&mut self, name: &str, value: &str) -> Result<String, String> {
+        let sig = self.find(name)?;
+        let state = self.engine.state().lock().unwrap();
+        let res = match sig {
+            Signal::Wire(id) => {
+                force(state.wires.data.get(&id).unwrap().borrow_mut().as_any_mut(), value)?;
+                state.wires.fmt_wire(id).unwrap()
+            },
+            Signal::Register(id) => {
+                force(state.registers.get(id).unwrap().borrow_mut().as_any_mut(), value)?;
+                state.registers.fmt_register(id)
+            },
+            Signal::Latch(id) => {
+                force(state.latches.get(id).unwrap().borrow_mut().as_any_mut(), value)?;
+                state.latches.fmt_latch(id)
+            },
+        };
+        Ok(format!("{} = {}", name, res))
+    }
+
+    fn release(&mut self, name: &str) -> Result<String, String> {
+        let sig = self.find(name)?;
+        let state = self.engine.state().lock().unwrap();
+        let forced = match sig {
+            Signal::Wire(id) => {
+                let mut s = state.wires.data.get(&id).unwrap().borrow_mut();
+                let forced = s.is_forced();
+                s.release();
+                forced
+            },
+            Signal::Register(id) => {
+                let mut s = state.registers.get(id).unwrap().borrow_mut();
+                let forced = s.is_forced();
+                s.release();
+                forced
+            },
+            Signal::Latch(_) => false,
+        };
+        if !forced {
+            return Err(format!("'{}' is not forced", name));
+        }
+        Ok(format!("released {}", name))
+    }
+
+    fn tasks(&self) -> String {
+        let mut lines: Vec<String> = self.engine.task_names()
+            .map(|name| format!("ready {}", name))
+            .collect();
+        for (name, cycle) in self.engine.sleeping_tasks() {
+            lines.push(format!("sleeping {} (until cycle {})", name, cycle));
+        }
+        for (name, wires) in self.engine.blocked_tasks() {
+            lines.push(format!("blocked {} on [{}]", name, wires.join(", ")));
+        }
+        if lines.is_empty() {
+            return "no pending tasks".to_string();
+        }
+        lines.join("\n")
+    }
+}
+
+/// Trait implemented on types that can be parsed by [`Debugger`].
+trait Value: Sized {
+    fn parse(s: &str) -> Option<Self>;
+}
+impl Value for bool {
+    fn parse(s: &str) -> Option<Self> { s.parse().ok() }
+}
+macro_rules! impl_value {
+    ($($ty:ty),*) => { $(
+        impl Value for $ty {
+            fn parse(s: &str) -> Option<Self> {
+                match s.strip_prefix("0x") {
+                    Some(hex) => <$ty>::from_str_radix(hex, 16).ok(),
+                    None => s.parse().ok(),
+                }
+            }
+        }
+    )* };
+}
+impl_value!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// Force a type-erased [`WireState`] or [`RegisterState`] (until released),
+/// or set the value of a type-erased [`LatchState`].
+fn force(state: &mut dyn Any, value: &str) -> Result<(), String> {
+    macro_rules! force {
+        ($($ty:ty),*) => { $(
+            let parsed = || <$ty as Value>::parse(value)
+                .ok_or_else(|| format!("invalid value '{}'", value));
+            if let Some(s) = state.downcast_mut::<WireState<$ty>>() {
+                let data = parsed()?;
+                s.forced = Some(data);
+                s.data = Some(data);
+                return Ok(());
+            }
+            if let Some(s) = state.downcast_mut::<RegisterState<$ty>>() {
+                let data = parsed()?;
+                s.forced = Some((data, None));
+                s.data = data;
+                s.next = None;
+                return Ok(());
+            }
+            if let Some(s) = state.downcast_mut::<LatchState<$ty>>() {
+                s.data = parsed()?;
+                return Ok(());
+            }
+        )* };
+    }
+    force!(bool, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+    Err("unsupported type".to_string())
+}
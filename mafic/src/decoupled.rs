@@ -0,0 +1,308 @@
+//! Ready/valid ("decoupled") channels.
+//!
+//! A [`Decoupled`] channel carries values of type `T` from a producer to a
+//! consumer. A value is transferred in a cycle where both `valid` (driven
+//! by the producer) and `ready` (driven by the consumer) are high.
+//!
+//! The [`Producer`] and [`Consumer`] halves of a channel perform the
+//! handshake over several cycles (see [`next_cycle`]):
+//!
+//! ```ignore
+//! e.schedule("producer", async {
+//!     for x in 0..4 { ch.producer().send(x).await; }
+//!     loop { ch.producer().idle().await; }
+//! });
+//! e.schedule("consumer", async {
+//!     loop { let x = ch.consumer().recv().await; ... }
+//! });
+//! ```
+//!
+//! [`PipelineReg`] and [`SkidBuffer`] are modules that buffer a single
+//! transfer between two channels.
+
+use mafic_derive::Bundle;
+
+use crate::check::Checker;
+use crate::engine::{ EngineState, next_cycle };
+use crate::module::{InstanceId, ModuleLike};
+use crate::register::RegisterId;
+use crate::wire::{ WireId, WireMap };
+
+/// A ready/valid channel carrying values of type `T`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Bundle)]
+pub struct Decoupled<T: Copy + std::fmt::Debug + 'static> {
+    /// Driven by the producer when `data` holds a value
+    pub valid: WireId<bool>,
+    /// Driven by the consumer when it can accept a value
+    #[bundle(flip)]
+    pub ready: WireId<bool>,
+    /// Driven by the producer when `valid` is high
+    pub data: WireId<T>,
+}
+impl <T: Copy + std::fmt::Debug + 'static> Decoupled<T> {
+    /// Allocate the wires for a new channel.
+    pub fn new(wires: &mut WireMap) -> Self {
+        Self {
+            valid: wires.alloc(),
+            ready: wires.alloc(),
+            data: wires.alloc(),
+        }
+    }
+
+    /// Allocate the wires for a new channel, named `{name}.valid`,
+    /// `{name}.ready`, and `{name}.data`.
+    pub fn new_named(wires: &mut WireMap, name: &str) -> Self {
+        Self {
+            valid: wires.alloc_named(format!("{}.valid", name)),
+            ready: wires.alloc_named(format!("{}.ready", name)),
+            data: wires.alloc_named(format!("{}.data", name)),
+        }
+    }
+
+    /// Return the producer half of this channel.
+    pub fn producer(&self) -> Producer<T> { Producer { ch: *self } }
+
+    /// Return the consumer half of this channel.
+    pub fn consumer(&self) -> Consumer<T> { Consumer { ch: *self } }
+
+    /// Returns 'true' if a value is transferred during this cycle.
+    pub async fn fire(&self) -> bool {
+        self.valid.sample().await && self.ready.sample().await
+    }
+}
+
+/// The producer half of a [`Decoupled`] channel.
+#[derive(Clone, Copy, Debug)]
+pub struct Producer<T: Copy + std::fmt::Debug + 'static> {
+    ch: Decoupled<T>,
+}
+impl <T: Copy + std::fmt::Debug + 'static> Producer<T> {
+    /// Drive `data` on the channel until the consumer accepts it.
+    ///
+    /// Completes at the start of the cycle after the transfer.
+    pub async fn send(&self, data: T) {
+        loop {
+            self.ch.valid.drive(true).await;
+            self.ch.data.drive(data).await;
+            let ready = self.ch.ready.sample().await;
+            next_cycle().await;
+            if ready {
+                return;
+            }
+        }
+    }
+
+    /// Send nothing during this cycle.
+    ///
+    /// Completes at the start of the next cycle.
+    pub async fn idle(&self) {
+        self.ch.valid.drive(false).await;
+        next_cycle().await;
+    }
+}
+
+/// The consumer half of a [`Decoupled`] channel.
+#[derive(Clone, Copy, Debug)]
+pub struct Consumer<T: Copy + std::fmt::Debug + 'static> {
+    ch: Decoupled<T>,
+}
+impl <T: Copy + std::fmt::Debug + 'static> Consumer<T> {
+    /// Accept the next value sent on the channel.
+    ///
+    /// Completes at the start of the cycle after the transfer.
+    pub async fn recv(&self) -> T {
+        loop {
+            self.ch.ready.drive(true).await;
+            if self.ch.valid.sample().await {
+                let data = self.ch.data.sample().await;
+                next_cycle().await;
+                return data;
+            }
+            next_cycle().await;
+        }
+    }
+
+    /// Accept nothing during this cycle (applying backpressure).
+    ///
+    /// Completes at the start of the next cycle.
+    pub async fn idle(&self) {
+        self.ch.ready.drive(false).await;
+        next_cycle().await;
+    }
+}
+
+/// A violation of the ready/valid protocol.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ProtocolError {
+    /// `valid` was dropped before the value was accepted
+    ValidDropped,
+    /// `data` changed before the value was accepted
+    DataChanged { from: String, to: String },
+    /// `valid` was high without any value on `data`
+    MissingData,
+}
+
+/// A violation observed by [`DecoupledChecker`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ProtocolViolation {
+    /// The cycle in which the violation was observed
+    pub cycle: usize,
+    /// The name of the channel
+    pub channel: String,
+    pub error: ProtocolError,
+}
+
+/// Checks that a producer follows the ready/valid protocol: once `valid`
+/// is high, both `valid` and `data` must be held until `ready` is high.
+pub struct DecoupledChecker<T: Copy + std::fmt::Debug + 'static> {
+    name: String,
+    ch: Decoupled<T>,
+    /// The value offered (but not accepted) during the previous cycle
+    pending: Option<T>,
+    /// Violations observed so far
+    pub violations: Vec<ProtocolViolation>,
+}
+impl <T: Copy + PartialEq + std::fmt::Debug + 'static> DecoupledChecker<T> {
+    pub fn new(name: impl Into<String>, ch: Decoupled<T>) -> Self {
+        Self { name: name.into(), ch, pending: None, violations: Vec::new() }
+    }
+
+    fn violation(&mut self, cycle: usize, error: ProtocolError) {
+        self.violations.push(ProtocolViolation {
+            cycle, channel: self.name.clone(), error
+        });
+    }
+}
+impl <T: Copy + PartialEq + std::fmt::Debug + 'static> Checker
+    for DecoupledChecker<T>
+{
+    fn check(&mut self, cycle: usize, state: &EngineState) {
+        let valid = state.wires.peek_wire(self.ch.valid) == Some(true);
+        let ready = state.wires.peek_wire(self.ch.ready) == Some(true);
+        let data = state.wires.peek_wire(self.ch.data);
+
+        if let Some(prev) = self.pending.take() {
+            if !valid {
+                self.violation(cycle, ProtocolError::ValidDropped);
+            } else if let Some(data) = data.filter(|d| *d != prev) {
+                self.violation(cycle, ProtocolError::DataChanged {
+                    from: format!("{:?}", prev), to: format!("{:?}", data),
+                });
+            }
+        }
+        if valid && data.is_none() {
+            self.violation(cycle, ProtocolError::MissingData);
+        }
+        if valid && !ready {
+            self.pending = data;
+        }
+    }
+}
+
+/// Registers a single transfer from `enq` to `deq`.
+///
+/// `enq.ready` depends on `deq.ready` in the same cycle, which allows one
+/// transfer per cycle.
+pub struct PipelineReg<T: Copy + std::fmt::Debug + 'static> {
+    pub enq: Decoupled<T>,
+    pub deq: Decoupled<T>,
+    full: RegisterId<bool>,
+    data: RegisterId<T>,
+    instance: InstanceId,
+}
+impl <T> ModuleLike for PipelineReg<T>
+    where T: Copy + Default + std::fmt::Debug + 'static
+{
+    fn new_instance(state: &mut EngineState) -> Self {
+        Self {
+            enq: Decoupled::new(&mut state.wires),
+            deq: Decoupled::new(&mut state.wires),
+            full: state.registers.alloc(false),
+            data: state.registers.alloc(T::default()),
+            instance: state.alloc_instance::<Self>(),
+        }
+    }
+    fn instance(&self) -> Option<InstanceId> { Some(self.instance) }
+    async fn run(&self) {
+        let full = self.full.sample().await;
+        self.deq.valid.drive(full).await;
+        if full {
+            self.deq.data.drive(self.data.sample().await).await;
+        }
+        let deq_ready = self.deq.ready.sample().await;
+        let enq_ready = !full || deq_ready;
+        self.enq.ready.drive(enq_ready).await;
+
+        if enq_ready && self.enq.valid.sample().await {
+            self.data.drive(self.enq.data.sample().await).await;
+            self.full.drive(true).await;
+        } else if full && deq_ready {
+            self.full.drive(false).await;
+        }
+    }
+}
+
+/// Buffers transfers from `enq` to `deq` with registered ready/valid
+/// signals.
+///
+/// Unlike [`PipelineReg`], `enq.ready` does not depend on `deq.ready` in
+/// the same cycle: a value accepted while `deq` is stalled is held in an
+/// extra "skid" register.
+pub struct SkidBuffer<T: Copy + std::fmt::Debug + 'static> {
+    pub enq: Decoupled<T>,
+    pub deq: Decoupled<T>,
+    out_valid: RegisterId<bool>,
+    out_data: RegisterId<T>,
+    skid_valid: RegisterId<bool>,
+    skid_data: RegisterId<T>,
+    instance: InstanceId,
+}
+impl <T> ModuleLike for SkidBuffer<T>
+    where T: Copy + Default + std::fmt::Debug + 'static
+{
+    fn new_instance(state: &mut EngineState) -> Self {
+        Self {
+            enq: Decoupled::new(&mut state.wires),
+            deq: Decoupled::new(&mut state.wires),
+            out_valid: state.registers.alloc(false),
+            out_data: state.registers.alloc(T::default()),
+            skid_valid: state.registers.alloc(false),
+            skid_data: state.registers.alloc(T::default()),
+            instance: state.alloc_instance::<Self>(),
+        }
+    }
+    fn instance(&self) -> Option<InstanceId> { Some(self.instance) }
+    async fn run(&self) {
+        let out_valid = self.out_valid.sample().await;
+        let skid_valid = self.skid_valid.sample().await;
+        self.deq.valid.drive(out_valid).await;
+        if out_valid {
+            self.deq.data.drive(self.out_data.sample().await).await;
+        }
+        self.enq.ready.drive(!skid_valid).await;
+
+        let deq_ready = self.deq.ready.sample().await;
+        let enq_data = if !skid_valid && self.enq.valid.sample().await {
+            Some(self.enq.data.sample().await)
+        } else {
+            None
+        };
+
+        if !out_valid || deq_ready {
+            // The output register is free
+            if skid_valid {
+                self.out_data.drive(self.skid_data.sample().await).await;
+                self.out_valid.drive(true).await;
+                self.skid_valid.drive(false).await;
+            } else if let Some(data) = enq_data {
+                self.out_data.drive(data).await;
+                self.out_valid.drive(true).await;
+            } else {
+                self.out_valid.drive(false).await;
+            }
+        } else if let Some(data) = enq_data {
+            self.skid_data.drive(data).await;
+            self.skid_valid.drive(true).await;
+        }
+    }
+}
@@ -1,23 +1,170 @@
 //! Implementation of a simulator. 
 
 use std::future::Future;
-use std::task::{ ContextBuilder, Waker };
+use std::task::{ Context, ContextBuilder, Poll, Waker };
 use std::pin::Pin;
+use std::borrow::Cow;
 
 use std::collections::*;
 use std::sync::*;
+use std::cell::RefCell;
 
 use crate::wire::*;
 use crate::register::*;
-use crate::module::ModuleLike;
+use crate::latch::LatchMap;
+use crate::module::{ self, InstanceId, ModuleLike };
+use crate::lint::{ Lint, LintReport };
+use crate::event::{ EngineEvent, EngineObserver, EventKind };
+use crate::check::Checker;
+use crate::clock::{ ClockDomain, ClockPhase, DomainCrossing, DomainId };
+use crate::random::{ Rng, SeedGuard, seed_from_env };
 
 /// Container for a future being executed by an [`Engine`]. 
 pub struct EngineTask<'a> { 
+    /// Unique identifier for this task
+    id: usize,
+
     /// Human-readable description of this task
-    name: &'static str,
+    name: Cow<'static, str>,
 
     /// The future associated with this task
     fut: Pin<Box<dyn Future<Output = ()> + 'a>>,
+
+    /// The clock domain of the logic described by this task (if any)
+    domain: Option<DomainId>,
+
+    /// Set for tasks which are scheduled again for the second half of a
+    /// cycle (see [`Engine::add_module`])
+    rerun: bool,
+}
+
+/// A hashed timer wheel: sleeping tasks are kept in one of a fixed number 
+/// of slots (indexed by the cycle they are waiting for), and only the slot 
+/// for the current cycle is visited at the start of each cycle. 
+#[derive(Debug)]
+pub struct TimerWheel { 
+    /// Each slot holds (cycle, task) for every task waiting for a cycle 
+    /// congruent to the index of the slot
+    slots: Vec<Vec<(usize, usize)>>,
+}
+impl Default for TimerWheel {
+    fn default() -> Self { 
+        Self { slots: vec![Vec::new(); Self::SLOTS] }
+    }
+}
+impl TimerWheel {
+    const SLOTS: usize = 64;
+
+    pub fn insert(&mut self, cycle: usize, task: usize) {
+        self.slots[cycle % Self::SLOTS].push((cycle, task));
+    }
+
+    pub fn remove(&mut self, cycle: usize, task: usize) {
+        self.slots[cycle % Self::SLOTS].retain(|t| *t != (cycle, task));
+    }
+
+    /// Remove and return all tasks waiting for the given cycle (tasks 
+    /// waiting for later cycles in the same slot are kept). 
+    pub fn expire(&mut self, cycle: usize) -> Vec<usize> {
+        let slot = &mut self.slots[cycle % Self::SLOTS];
+        let mut tasks = Vec::new();
+        slot.retain(|(c, task)| {
+            let due = *c <= cycle;
+            if due {
+                tasks.push(*task);
+            }
+            !due
+        });
+        tasks
+    }
+
+    /// Returns 'true' if no task is waiting.
+    pub fn is_empty(&self) -> bool {
+        self.slots.iter().all(|s| s.is_empty())
+    }
+}
+
+/// Tracks tasks which are blocked waiting for a wire to be driven. 
+#[derive(Default, Debug)]
+pub struct WaitMap { 
+    /// The task currently being polled by an [`Engine`]
+    pub current: Option<usize>,
+
+    /// Tasks blocked on each wire
+    pub waiters: BTreeMap<usize, BTreeSet<usize>>,
+
+    /// Wires that each blocked task is waiting on
+    pub blocked: BTreeMap<usize, BTreeSet<usize>>,
+
+    /// Tasks which have been woken up since they were last collected
+    pub woken: Vec<usize>,
+
+    /// The cycle that each sleeping task is waiting for
+    pub sleeping: BTreeMap<usize, usize>,
+
+    /// Sleeping tasks, keyed by the cycle they are waiting for
+    pub timers: TimerWheel,
+}
+impl WaitMap {
+    /// Block the current task until the given wire is driven.
+    pub fn wait(&mut self, wire: usize) {
+        if let Some(task) = self.current {
+            self.waiters.entry(wire).or_default().insert(task);
+            self.blocked.entry(task).or_default().insert(wire);
+        }
+    }
+
+    /// Wake all tasks blocked on the given wire.
+    pub fn wake(&mut self, wire: usize) {
+        let Some(tasks) = self.waiters.remove(&wire) else { return };
+        for task in tasks {
+            self.forget(task);
+            self.woken.push(task);
+        }
+    }
+
+    /// Suspend the current task until the start of the given cycle. 
+    ///
+    /// A task waiting on several timers (ie. with select) wakes up at the 
+    /// earliest one.
+    pub fn sleep_until(&mut self, cycle: usize) {
+        if let Some(task) = self.current {
+            if self.sleeping.get(&task).is_some_and(|c| *c <= cycle) {
+                return;
+            }
+            self.cancel_sleep(task);
+            self.sleeping.insert(task, cycle);
+            self.timers.insert(cycle, task);
+        }
+    }
+
+    /// Remove a sleeping task (ie. after it was woken up by a wire). 
+    pub fn cancel_sleep(&mut self, task: usize) {
+        if let Some(cycle) = self.sleeping.remove(&task) {
+            self.timers.remove(cycle, task);
+        }
+    }
+
+    /// Remove and return all tasks waiting for the given cycle.
+    pub fn expire(&mut self, cycle: usize) -> Vec<usize> {
+        let tasks = self.timers.expire(cycle);
+        for task in &tasks {
+            self.sleeping.remove(task);
+        }
+        tasks
+    }
+
+    /// Remove all wires that the given task is blocked on.
+    pub fn forget(&mut self, task: usize) {
+        for wire in self.blocked.remove(&task).unwrap_or_default() {
+            if let Some(w) = self.waiters.get_mut(&wire) {
+                w.remove(&task);
+                if w.is_empty() {
+                    self.waiters.remove(&wire);
+                }
+            }
+        }
+    }
 }
 
 /// Container for simulated state. 
@@ -27,12 +174,200 @@ pub struct EngineState {
 
     /// Tracks the state of all registers
     pub registers: RegisterMap,
+
+    /// Tracks the state of all latches
+    pub latches: LatchMap,
+
+    /// The current clock cycle
+    pub cycle: usize,
+
+    /// When set, writes to wires are recorded in `events`
+    pub trace_wires: bool,
+
+    /// When set, drives to forced wires/registers are recorded in `events`
+    pub trace_forced: bool,
+
+    /// Events waiting to be delivered to observers
+    pub events: RefCell<Vec<EngineEvent>>,
+
+    /// Tasks waiting for wires to be driven
+    pub waits: RefCell<WaitMap>,
+
+    /// When set, four-state wires which are never driven read as Z
+    pub four_state: bool,
+
+    /// Clock domains (indexed by [`DomainId`])
+    pub domains: Vec<ClockDomain>,
+
+    /// The clock domain of the task currently being polled (if any)
+    pub current_domain: Option<DomainId>,
+
+    /// The clock domain of the task which drove each wire during this 
+    /// cycle, keyed by the root of each net
+    pub wire_domains: RefCell<BTreeMap<usize, DomainId>>,
+
+    /// Signals sampled by logic in a different clock domain during this 
+    /// cycle
+    pub crossings: RefCell<Vec<DomainCrossing>>,
+
+    /// Generator for random stimulus (see [`crate::random`])
+    pub rng: Rng,
+
+    /// The half of the current cycle (see [`crate::clock::ClockEdge`])
+    pub phase: ClockPhase,
+
+    /// Number of module instances allocated for each type name
+    instance_counts: BTreeMap<&'static str, usize>,
+
+    /// Set while polling a task which is scheduled again for the second 
+    /// half of a cycle (see [`Engine::add_module`])
+    pub current_rerun: bool,
+
+    /// Nets driven during the first half of this cycle by tasks which are 
+    /// not scheduled again, keyed by their root. These keep their values 
+    /// for the second half. 
+    pub held_wires: RefCell<BTreeSet<usize>>,
+
+    /// Registers driven during the first half of this cycle by tasks which 
+    /// are not scheduled again
+    pub held_registers: RefCell<BTreeSet<usize>>,
 }
 impl EngineState {
     fn new() -> Self { 
         Self { 
             wires: WireMap::new(),
             registers: RegisterMap::new(),
+            latches: LatchMap::new(),
+            cycle: 0,
+            trace_wires: false,
+            trace_forced: false,
+            events: RefCell::new(Vec::new()),
+            waits: RefCell::new(WaitMap::default()),
+            four_state: false,
+            domains: vec![ClockDomain { 
+                name: "clk".to_string(), period: 1, phase: 0 
+            }],
+            current_domain: None,
+            wire_domains: RefCell::new(BTreeMap::new()),
+            crossings: RefCell::new(Vec::new()),
+            rng: Rng::new(seed_from_env()),
+            phase: ClockPhase::High,
+            instance_counts: BTreeMap::new(),
+            current_rerun: false,
+            held_wires: RefCell::new(BTreeSet::new()),
+            held_registers: RefCell::new(BTreeSet::new()),
+        }
+    }
+
+    /// Returns 'true' if values driven by the current task must be held 
+    /// for the second half of this cycle. 
+    pub(crate) fn holds_drives(&self) -> bool {
+        self.phase == ClockPhase::High && !self.current_rerun 
+            && self.registers.has_negedge()
+    }
+
+    /// Allocate an identifier for an instance of module `M` (see 
+    /// [`ModuleLike::instance`]). 
+    ///
+    /// Instances are numbered in the order they are allocated, and are 
+    /// named after their type with this number (ie. `ROM[0]`). 
+    pub fn alloc_instance<M: ?Sized>(&mut self) -> InstanceId {
+        InstanceId(self.alloc_instance_index(module::type_name::<M>()))
+    }
+
+    /// Return the next index for an instance of the given type.
+    pub(crate) fn alloc_instance_index(&mut self, ty: &'static str) -> usize {
+        let idx = self.instance_counts.entry(ty).or_insert(0);
+        *idx += 1;
+        *idx - 1
+    }
+
+    /// Replace the random generator with one using the given seed.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    /// Add a clock domain with an edge every `period` ticks of the base 
+    /// clock, starting at tick `phase`. 
+    pub fn add_domain(&mut self, name: impl Into<String>, period: usize, 
+        phase: usize) -> DomainId 
+    {
+        assert!(period > 0, "clock period must be non-zero");
+        self.domains.push(ClockDomain { name: name.into(), period, phase });
+        DomainId(self.domains.len() - 1)
+    }
+
+    /// Return a clock domain.
+    pub fn domain(&self, id: DomainId) -> &ClockDomain {
+        &self.domains[id.0]
+    }
+
+    /// Returns 'true' if the given clock domain has an edge at the end of 
+    /// the current tick.
+    pub fn is_edge(&self, id: DomainId) -> bool {
+        self.domains[id.0].is_edge(self.cycle)
+    }
+
+    /// Record a register being sampled by logic in a different clock domain.
+    pub fn note_register_read(&self, id: usize) {
+        let Some(to) = self.current_domain else { return };
+        let from = self.registers.domain(id);
+        if from != to {
+            self.crossings.borrow_mut().push(DomainCrossing {
+                cycle: self.cycle, 
+                signal: self.registers.name(id)
+                    .map_or_else(|| format!("reg{}", id), |s| s.to_string()),
+                from, to,
+            });
+        }
+    }
+
+    /// Record a wire being sampled by logic in a different clock domain 
+    /// than the logic driving it.
+    fn note_wire_read(&self, id: usize) {
+        let Some(to) = self.current_domain else { return };
+        let from = self.wire_domains.borrow().get(&self.wires.root(id)).copied();
+        if let Some(from) = from.filter(|from| *from != to) {
+            self.crossings.borrow_mut().push(DomainCrossing {
+                cycle: self.cycle, 
+                signal: self.wires.name(id)
+                    .map_or_else(|| format!("wire{}", id), |s| s.to_string()),
+                from, to,
+            });
+        }
+    }
+
+    /// Enable/disable four-state simulation. 
+    ///
+    /// When enabled, registers allocated afterwards with 
+    /// [`RegisterMap::alloc_four_state`] power up as X, and wires allocated
+    /// with [`WireMap::alloc_four_state`] read as Z when no process drives
+    /// them. 
+    pub fn set_four_state(&mut self, enable: bool) {
+        self.four_state = enable;
+        self.registers.power_up_x = enable;
+    }
+
+    /// Give the undriven four-state wire with the lowest identifier that 
+    /// some task is blocked on its "undriven" value, and wake up the 
+    /// blocked tasks. 
+    ///
+    /// Only one wire is resolved at a time, since the woken tasks may 
+    /// go on to drive the other wires. A blocked task may still drive a 
+    /// resolved wire later (ie. when it is waiting on a different wire): 
+    /// the driven value replaces the undriven value, but tasks which 
+    /// sampled the wire in the meantime have already seen the undriven 
+    /// value. 
+    ///
+    /// Returns 'true' if any task was woken up. 
+    pub fn resolve_undriven(&self) -> bool {
+        let mut waits = self.waits.borrow_mut();
+        let wire = waits.waiters.keys().copied().find(|id| {
+            self.wires.data.get(id).unwrap().borrow_mut().resolve_undriven()
+        });
+        match wire {
+            Some(id) => { waits.wake(id); true },
+            None => false,
         }
     }
     pub fn new_shareable() -> Arc<Mutex<Self>> {
@@ -51,6 +386,10 @@ impl EngineState {
 
         // Downcast the wire's state into the concrete type
         let s = s.as_any_mut().downcast_mut::<WireState<T>>().unwrap();
+        s.sampled = true;
+        if s.data.is_some() {
+            self.note_wire_read(wire.id());
+        }
         s.data
     }
 
@@ -67,11 +406,112 @@ impl EngineState {
         // Downcast the wire's state into the concrete type
         let s = s.as_any_mut().downcast_mut::<WireState<T>>().unwrap();
 
-        // Write the data. 
+        // Drives to a forced wire are ignored (and are not conflicts)
+        if s.forced.is_some() {
+            self.note_ignored_drive(|| self.wires.name(wire.id())
+                .map_or_else(|| format!("wire{}", wire.id()), |s| s.to_string()), &data);
+            return;
+        }
+
+        // Write the data. A driven value replaces the undriven value given 
+        // to a four-state wire (see [`EngineState::resolve_undriven`]).
         // FIXME: If the wire has already been assigned a value, just panic. 
-        if let Some(data) = s.data.replace(data) {
+        let resolved = std::mem::take(&mut s.resolved);
+        if s.data.replace(data).is_some() && !resolved {
             panic!("driver-to-driver error {:?}, {:x?}", wire, *s);
         } 
+        self.waits.borrow_mut().wake(self.wires.root(wire.id()));
+        if self.holds_drives() {
+            self.held_wires.borrow_mut().insert(self.wires.root(wire.id()));
+        }
+        if let Some(d) = self.current_domain {
+            self.wire_domains.borrow_mut().insert(self.wires.root(wire.id()), d);
+        }
+
+        if self.trace_wires {
+            self.events.borrow_mut().push(EngineEvent::WireDriven {
+                cycle: self.cycle,
+                id: wire.id(),
+                name: self.wires.name(wire.id()).map(|s| s.to_string()),
+                value: format!("{:?}", data),
+            });
+        }
+    }
+
+    /// Record a drive to a forced wire/register which was ignored.
+    pub(crate) fn note_ignored_drive(&self, signal: impl FnOnce() -> String, 
+        data: &dyn std::fmt::Debug) 
+    {
+        if self.trace_forced {
+            self.events.borrow_mut().push(EngineEvent::DriveIgnored {
+                cycle: self.cycle,
+                signal: signal(),
+                value: format!("{:?}", data),
+            });
+        }
+    }
+
+    /// Force a wire to a value until it is released, regardless of its 
+    /// drivers (like `force` in Verilog). 
+    ///
+    /// Drives to a forced wire are ignored (see [`EventKind::DriveIgnored`]),
+    /// and the forced value is visible on the wire on every cycle. Forcing 
+    /// a wire forces every wire in its net. 
+    pub fn force_wire<T: Copy + std::fmt::Debug + 'static>(
+        &self, wire: WireId<T>, data: T
+    )
+    {
+        {
+            let mut s = self.wires.data.get(&wire.id()).unwrap().borrow_mut();
+            let s = s.as_any_mut().downcast_mut::<WireState<T>>().unwrap();
+            s.forced = Some(data);
+            s.data = Some(data);
+        }
+        self.waits.borrow_mut().wake(self.wires.root(wire.id()));
+    }
+
+    /// Stop forcing a wire. 
+    ///
+    /// The wire is undriven until the next drive, so drives which were 
+    /// ignored earlier in the current cycle are lost. 
+    pub fn release_wire<T: Copy + std::fmt::Debug + 'static>(&self, wire: WireId<T>) {
+        self.wires.data.get(&wire.id()).unwrap().borrow_mut().release();
+    }
+
+    /// Force a register to a value, regardless of its drivers. 
+    ///
+    /// The register is released after `cycles` updates (on the edges of its
+    /// clock), or when `cycles` is `None`, by [`EngineState::release_register`].
+    /// Drives to a forced register are ignored (see 
+    /// [`EventKind::DriveIgnored`]). 
+    pub fn force_register<T: Copy + std::fmt::Debug + 'static>(
+        &self, reg: RegisterId<T>, data: T, cycles: Option<usize>
+    )
+    {
+        assert!(cycles != Some(0), "cannot force a register for 0 cycles");
+        let mut s = self.registers.get(reg.id()).unwrap().borrow_mut();
+        let s = s.as_any_mut().downcast_mut::<RegisterState<T>>().unwrap();
+        s.forced = Some((data, cycles));
+        s.data = data;
+        s.next = None;
+    }
+
+    /// Stop forcing a register. The register keeps the forced value until 
+    /// it is driven again. 
+    pub fn release_register<T: Copy + std::fmt::Debug + 'static>(
+        &self, reg: RegisterId<T>
+    )
+    {
+        self.registers.get(reg.id()).unwrap().borrow_mut().release();
+    }
+
+    /// Block the task currently being polled until the given wire is driven.
+    ///
+    /// Futures which return [`Poll::Pending`](std::task::Poll::Pending) 
+    /// while waiting for a wire are expected to call this; otherwise, the
+    /// engine must repeatedly poll the task until it completes. 
+    pub fn wait_on_wire(&self, id: usize) {
+        self.waits.borrow_mut().wait(self.wires.root(id));
     }
 
     /// Invalidate data for the given wire
@@ -93,13 +533,115 @@ impl EngineState {
 
 
 
+}
+
+/// Future which completes at the start of a later cycle (see [`delay`], 
+/// [`next_cycle`], and [`wait_until_cycle`]).
+pub struct DelayFuture {
+    /// Number of cycles to wait (from the cycle of the first poll)
+    delay: usize,
+    /// The cycle in which this future completes
+    target: Option<usize>,
+}
+impl Future for DelayFuture {
+    type Output = ();
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<()> {
+        let state: &mut Arc<Mutex<EngineState>> = ctx.ext().downcast_mut().unwrap();
+        let state = state.lock().unwrap();
+        let delay = self.delay;
+        let target = *self.target.get_or_insert(state.cycle + delay);
+        if state.cycle >= target {
+            Poll::Ready(())
+        } else {
+            state.waits.borrow_mut().sleep_until(target);
+            Poll::Pending
+        }
+    }
+}
+
+/// Future which completes at the start of the next cycle (see [`next_cycle`]).
+pub type NextCycleFuture = DelayFuture;
+
+/// Wait until the start of the next cycle. 
+///
+/// This allows a single task to describe behavior over several cycles 
+/// (ie. a handshake). The task is suspended when the current cycle ends, 
+/// and resumes after registers have been updated and wires have been reset. 
+pub fn next_cycle() -> NextCycleFuture {
+    delay(1)
+}
+
+/// Wait until the start of the cycle `n` cycles from now. 
+///
+/// The task is not polled again until then. Completes immediately when `n`
+/// is zero. 
+pub fn delay(n: usize) -> DelayFuture {
+    DelayFuture { delay: n, target: None }
+}
+
+/// Wait until the start of the given cycle. 
+///
+/// Completes immediately if the cycle has already started. 
+pub fn wait_until_cycle(cycle: usize) -> DelayFuture {
+    DelayFuture { delay: 0, target: Some(cycle) }
+}
+
+/// Return the current cycle.
+pub fn current_cycle() -> impl Future<Output = usize> {
+    std::future::poll_fn(|ctx| {
+        let state: &mut Arc<Mutex<EngineState>> = ctx.ext().downcast_mut().unwrap();
+        Poll::Ready(state.lock().unwrap().cycle)
+    })
 }
 
 #[derive(Debug)]
 pub enum EngineErr { 
-
+    /// Some tasks are blocked on wires that will never be driven. 
+    Stall { 
+        /// The cycle in which the stall occurred
+        cycle: usize,
+        /// The name of each blocked task, and the wires it is blocked on
+        blocked: Vec<(String, Vec<String>)>,
+    },
+    /// A condition did not hold within the cycle budget (see 
+    /// [`Engine::run_until`]). 
+    Timeout { 
+        /// The cycle in which the simulation was stopped
+        cycle: usize,
+        /// The number of cycles simulated
+        budget: usize,
+    },
+}
+impl std::fmt::Display for EngineErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Stall { cycle, blocked } => {
+                write!(f, "stall in cycle {}:", cycle)?;
+                for (task, wires) in blocked {
+                    write!(f, " '{}' blocked on [{}];", task, wires.join(", "))?;
+                }
+                Ok(())
+            },
+            Self::Timeout { cycle, budget } => {
+                write!(f, "timeout in cycle {}: condition did not hold within {} cycles", 
+                    cycle, budget)
+            },
+        }
+    }
 }
+impl std::error::Error for EngineErr {}
+
 
+/// A module which can be scheduled on an [`Engine`] (with the type of the 
+/// module erased). 
+trait ScheduleModule {
+    fn push_to<'s>(&'s self, e: &mut Engine<'s>, domain: Option<DomainId>);
+}
+impl <M: ModuleLike> ScheduleModule for M {
+    fn push_to<'s>(&'s self, e: &mut Engine<'s>, domain: Option<DomainId>) {
+        e.push_module(self, domain, true);
+    }
+}
 
 /// A [wildly inefficient] `async` executor that completes the simulated logic
 /// described by types implementing [`ModuleLike`]. 
@@ -121,6 +663,10 @@ pub enum EngineErr {
 ///   engine switches to a different task (and ideally, switches to a task
 ///   that causes forward-progress through the simulation). 
 ///
+/// - A task which is waiting for a wire to be driven is *blocked* until
+///   some other task drives the wire (see [`EngineState::wait_on_wire`]). 
+///   If only blocked tasks remain, the simulation has stalled. 
+///
 /// - When the task queue has been emptied, it means that values have 
 ///   successfully propagated through all tasks, and all tasks have driven
 ///   writes to registers. 
@@ -129,18 +675,58 @@ pub enum EngineErr {
 ///   reset the state of all wires, and then reschedule the logic for all 
 ///   modules to be performed again on the next cycle.
 ///
+/// - Alternatively, a task can describe behavior across several cycles by
+///   awaiting [`next_cycle`] (or [`delay`]). The task is set aside in a 
+///   [`TimerWheel`], and then moved back into the queue at the start of the
+///   cycle it is waiting for. 
+///
 pub struct Engine<'a> {
     /// Queue of tasks associated with pending futures
     tasks: VecDeque<EngineTask<'a>>,
 
+    /// Tasks blocked on wires, keyed by task identifier
+    blocked: BTreeMap<usize, EngineTask<'a>>,
+
+    /// Tasks waiting for the next cycle, keyed by task identifier
+    sleeping: BTreeMap<usize, EngineTask<'a>>,
+
     /// Simulated state
     state: Arc<Mutex<EngineState>>,
 
-    /// Number of scheduler steps
+    /// Number of scheduler steps during the current cycle
     steps: usize,
 
-    /// Number of clock cycles
-    cycles: usize,
+    /// Maximum number of scheduler steps in a single cycle
+    step_limit: usize,
+
+    /// Identifier for the next scheduled task
+    next_task_id: usize,
+
+    /// Observers receiving events
+    observers: Vec<Box<dyn EngineObserver + 'a>>,
+
+    /// Checks performed at the end of each cycle
+    checkers: Vec<Box<dyn Checker + 'a>>,
+
+    /// Optional lint pass
+    lint: Option<Lint>,
+
+    /// Names assigned to unnamed module instances without an 
+    /// [`InstanceId`], keyed by the address and type name of each instance
+    instances: BTreeMap<(usize, &'static str), String>,
+
+    /// Modules added with [`Engine::add_module`], which are scheduled on 
+    /// every cycle
+    modules: Vec<(&'a dyn ScheduleModule, Option<DomainId>)>,
+
+    /// Maximum number of cycles simulated by [`Engine::run_until`]
+    cycle_budget: usize,
+
+    /// Shuffles the order in which tasks are polled (if enabled)
+    order: Option<Rng>,
+
+    /// Prints the seeds if the simulation panics
+    seeds: SeedGuard,
 }
 impl <'a> Engine<'a> {
 
@@ -148,65 +734,423 @@ impl <'a> Engine<'a> {
     pub fn new(state: Arc<Mutex<EngineState>>) -> Engine<'a> {
         Engine {
             tasks: VecDeque::new(),
-            state,
+            blocked: BTreeMap::new(),
+            sleeping: BTreeMap::new(),
+            state: state.clone(),
             steps: 0,
-            cycles: 0,
+            step_limit: 1 << 16,
+            next_task_id: 0,
+            observers: Vec::new(),
+            checkers: Vec::new(),
+            lint: None,
+            instances: BTreeMap::new(),
+            modules: Vec::new(),
+            cycle_budget: 1 << 20,
+            order: None,
+            seeds: SeedGuard { state: state.clone(), order_seed: None },
         }
     }
 
     /// Schedule some [arbitrary] future `F`. 
     pub fn schedule<F: Future<Output = ()> + 'a>
-        (&mut self, name: &'static str, fut: F) 
+        (&mut self, name: impl Into<Cow<'static, str>>, fut: F) 
     {
-        let t = EngineTask { name, fut: Box::pin(fut) };
+        self.push_task(name.into(), Box::pin(fut), None, false);
+    }
+
+    /// Schedule some [arbitrary] future `F` describing logic in the given
+    /// clock domain. 
+    ///
+    /// Registers and wires from other domains sampled by this task are 
+    /// recorded as [`DomainCrossing`]s (see 
+    /// [`CdcChecker`](crate::cdc::CdcChecker)). 
+    pub fn schedule_in<F: Future<Output = ()> + 'a>(&mut self, 
+        domain: DomainId, name: impl Into<Cow<'static, str>>, fut: F) 
+    {
+        self.push_task(name.into(), Box::pin(fut), Some(domain), false);
+    }
+
+    fn push_task(&mut self, name: Cow<'static, str>, 
+        fut: Pin<Box<dyn Future<Output = ()> + 'a>>, domain: Option<DomainId>,
+        rerun: bool) 
+    {
+        let t = EngineTask { id: self.alloc_task_id(), name, fut, domain, rerun };
         self.tasks.push_back(t);
     }
 
     /// Schedule an instance of some module.  
-    pub fn schedule_module(&mut self, module: &'a impl ModuleLike) {
-        let fut = Box::pin(module.run());
-        let task = EngineTask { 
-            name: "",
-            fut
+    ///
+    /// Each process returned by [`ModuleLike::processes`] is scheduled as 
+    /// a separate task. 
+    ///
+    /// Tasks are named with [`ModuleLike::name`]. Unnamed instances are
+    /// named after their type with an index (ie. `ROM[0]`), which stays the 
+    /// same for a particular instance across cycles (see 
+    /// [`ModuleLike::instance`]). 
+    pub fn schedule_module<M: ModuleLike>(&mut self, module: &'a M) {
+        self.push_module(module, None, false);
+    }
+
+    /// Schedule an instance of some module whose logic belongs to the 
+    /// given clock domain (see [`Engine::schedule_in`]). 
+    ///
+    /// Modules crossing between clock domains (ie. the primitives in 
+    /// [`cdc`](crate::cdc)) should be scheduled with 
+    /// [`Engine::schedule_module`] instead. 
+    pub fn schedule_module_in<M: ModuleLike>(&mut self, domain: DomainId, 
+        module: &'a M) 
+    {
+        self.push_module(module, Some(domain), false);
+    }
+
+    /// Schedule an instance of some module on every cycle, starting with 
+    /// the current cycle. 
+    ///
+    /// In half-cycle mode (see [`Engine::try_step`]), the module is also 
+    /// scheduled again for the second half of each cycle. 
+    pub fn add_module<M: ModuleLike>(&mut self, module: &'a M) {
+        self.push_module(module, None, true);
+        self.modules.push((module, None));
+    }
+
+    /// Schedule an instance of some module on every cycle, starting with 
+    /// the current cycle (see [`Engine::schedule_module_in`]). 
+    pub fn add_module_in<M: ModuleLike>(&mut self, domain: DomainId, 
+        module: &'a M) 
+    {
+        self.push_module(module, Some(domain), true);
+        self.modules.push((module, Some(domain)));
+    }
+
+    fn push_module<M: ModuleLike>(&mut self, module: &'a M, 
+        domain: Option<DomainId>, rerun: bool) 
+    {
+        let name = match module.name() {
+            Some(name) => name,
+            None => self.instance_name(module),
         };
-        self.tasks.push_back(task);
+        for (proc_name, fut) in module.processes() {
+            let name = if proc_name.is_empty() { 
+                name.clone()
+            } else { 
+                format!("{}.{}", name, proc_name)
+            };
+            self.push_task(Cow::Owned(name), fut, domain, rerun);
+        }
+    }
+
+    fn alloc_task_id(&mut self) -> usize {
+        let id = self.next_task_id;
+        self.next_task_id += 1;
+        id
+    }
+
+    /// Return the default name for an unnamed module instance. 
+    fn instance_name<M: ModuleLike>(&mut self, module: &'a M) -> String {
+        let ty = module::type_name::<M>();
+        if let Some(id) = module.instance() {
+            return format!("{}[{}]", ty, id.index());
+        }
+        let state = &self.state;
+        self.instances.entry((module as *const M as usize, ty))
+            .or_insert_with(|| {
+                let idx = state.lock().unwrap().alloc_instance_index(ty);
+                format!("{}[{}]", ty, idx)
+            }).clone()
+    }
+
+    /// Return the names of all tasks waiting to be completed.
+    pub fn task_names(&self) -> impl Iterator<Item = &str> {
+        self.tasks.iter().map(|t| t.name.as_ref())
+    }
+
+    /// Poll tasks in a random order, or in scheduling order when `seed` is
+    /// `None` (the default). 
+    ///
+    /// When enabled, the task queue is shuffled at the start of each cycle,
+    /// and tasks which are re-polled or woken up are moved to a random 
+    /// position in the queue. Correctly-written models should behave the 
+    /// same under any order (see [`crate::order`]). 
+    pub fn set_task_order(&mut self, seed: Option<u64>) {
+        self.order = seed.map(Rng::new);
+        self.seeds.order_seed = seed;
+    }
+
+    /// Add a task to the queue (at a random position, when the task order
+    /// is shuffled). 
+    fn requeue(&mut self, task: EngineTask<'a>) {
+        match &mut self.order {
+            Some(rng) => {
+                let idx = rng.below(self.tasks.len() as u64 + 1) as usize;
+                self.tasks.insert(idx, task);
+            },
+            None => self.tasks.push_back(task),
+        }
+    }
+
+    /// Set the maximum number of times that tasks can be re-polled in
+    /// a single cycle. 
+    pub fn set_step_limit(&mut self, limit: usize) {
+        self.step_limit = limit;
     }
 
     /// Perform a single simulated clock-cycle by running tasks until the
     /// queue is emptied (and all pending futures have completed). 
+    ///
+    /// Panics if the simulation stalls (see [`Engine::try_run`]). 
     pub fn run(&mut self) {
+        if let Err(e) = self.try_run() {
+            panic!("{}", e);
+        }
+    }
+
+    /// Perform a single simulated clock-cycle by running tasks until the
+    /// queue is emptied (and all pending futures have completed). 
+    ///
+    /// Returns [`EngineErr::Stall`] if only tasks blocked on wires remain.
+    pub fn try_run(&mut self) -> Result<(), EngineErr> {
 
         // NOTE: Depends on the 'noop_waker' feature
         let waker = Waker::noop();
 
         // NOTE: Depends on the 'context_ext' and 'local_waker' features
-        let mut cx = ContextBuilder::from_waker(&waker)
-            .ext(&mut self.state).build();
+        let mut state = self.state.clone();
+        let mut cx = ContextBuilder::from_waker(waker)
+            .ext(&mut state).build();
+        let cycle = self.cycles();
+        self.steps = 0;
+        if let Some(rng) = &mut self.order {
+            for i in (1..self.tasks.len()).rev() {
+                let j = rng.below(i as u64 + 1) as usize;
+                self.tasks.swap(i, j);
+            }
+        }
 
-        // Just cycle through tasks until we [hopefully] terminate. 
+        // Cycle through tasks until we terminate. 
         //
-        // NOTE: At some point, you should probably be smarter about this.
-        // Also, it's easy to imagine cases where the user may unintentionally
-        // create stall conditions. 
-        while let Some(mut task) = self.tasks.pop_front() {
+        // Tasks blocked on a wire are set aside until the wire is driven. 
+        // Tasks that are pending without being blocked on any wire are just 
+        // polled again later. 
+        loop {
+            let Some(mut task) = self.tasks.pop_front() else {
+                // In four-state simulation, blocked tasks may be waiting
+                // on wires that are never driven
+                if self.resolve_undriven() { 
+                    continue; 
+                }
+                break;
+            };
 
-            // FIXME: For now, just limit the number of steps. 
-            assert!(self.steps < 32, "step limit");
+            // NOTE: Only tasks which never block can hit this limit
+            assert!(self.steps < self.step_limit, "step limit");
 
             // try to complete a task
-            println!("polling {}", task.name);
-            if task.fut.as_mut().poll(&mut cx).is_pending() {
-                self.tasks.push_back(task);
+            self.emit(EventKind::TaskPolled, || EngineEvent::TaskPolled { 
+                cycle, id: task.id, name: task.name.clone() 
+            });
+            {
+                let mut state = self.state.lock().unwrap();
+                state.waits.borrow_mut().current = Some(task.id);
+                state.current_domain = task.domain;
+                state.current_rerun = task.rerun;
+            }
+            let pending = task.fut.as_mut().poll(&mut cx).is_pending();
+            let (blocked, asleep, woken) = {
+                let mut state = self.state.lock().unwrap();
+                state.current_domain = None;
+                state.current_rerun = false;
+                let mut waits = state.waits.borrow_mut();
+                waits.current = None;
+                if !pending {
+                    waits.forget(task.id);
+                }
+                (waits.blocked.contains_key(&task.id), 
+                 waits.sleeping.contains_key(&task.id),
+                 std::mem::take(&mut waits.woken))
+            };
+            self.deliver_wire_events();
+            if pending {
+                self.emit(EventKind::TaskPending, || EngineEvent::TaskPending { 
+                    cycle, id: task.id, name: task.name.clone() 
+                });
+                if asleep {
+                    self.sleeping.insert(task.id, task);
+                } else if blocked { 
+                    self.blocked.insert(task.id, task);
+                } else { 
+                    self.requeue(task);
+                }
                 self.steps += 1;
             } else { 
-                println!("completed {}", task.name);
+                self.emit(EventKind::TaskCompleted, || EngineEvent::TaskCompleted { 
+                    cycle, id: task.id, name: task.name.clone() 
+                });
+            }
+
+            self.wake_tasks(woken);
+        }
+
+        if self.blocked.is_empty() {
+            Ok(())
+        } else { 
+            Err(EngineErr::Stall { cycle, blocked: self.blocked_tasks() })
+        }
+    }
+
+    /// Move woken tasks back into the queue.
+    fn wake_tasks(&mut self, woken: Vec<usize>) {
+        for id in woken {
+            if let Some(task) = self.blocked.remove(&id) {
+                self.requeue(task);
+            } else if let Some(task) = self.sleeping.remove(&id) {
+                // Also waiting on a wire (ie. with select)
+                {
+                    let state = self.state.lock().unwrap();
+                    state.waits.borrow_mut().cancel_sleep(id);
+                }
+                self.requeue(task);
             }
         }
     }
 
-    /// Reset the state of all wires.
+    /// Move tasks waiting for the current cycle back into the queue.
+    fn wake_sleeping(&mut self) {
+        let state = self.state.lock().unwrap();
+        let mut waits = state.waits.borrow_mut();
+        for id in waits.expire(state.cycle) {
+            waits.forget(id);
+            if let Some(task) = self.sleeping.remove(&id) {
+                self.tasks.push_back(task);
+            }
+        }
+    }
+
+    /// In four-state simulation, resolve undriven wires that blocked tasks
+    /// are waiting on. Returns 'true' if any task was woken up.
+    fn resolve_undriven(&mut self) -> bool {
+        if self.blocked.is_empty() {
+            return false;
+        }
+        let woken = {
+            let state = self.state.lock().unwrap();
+            if !state.four_state || !state.resolve_undriven() {
+                return false;
+            }
+            std::mem::take(&mut state.waits.borrow_mut().woken)
+        };
+        self.wake_tasks(woken);
+        true
+    }
+
+    /// Attach a [`Checker`] which inspects the simulated state at the end
+    /// of each cycle. 
+    ///
+    /// Pass an `Rc<RefCell<C>>` to keep a handle to the checker. 
+    pub fn add_checker(&mut self, checker: impl Checker + 'a) {
+        self.checkers.push(Box::new(checker));
+    }
+
+    /// Reset all registers and latches to their reset values.
+    pub fn reset_registers(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.registers.reset();
+        state.latches.reset();
+    }
+
+    /// Return the simulated state.
+    pub fn state(&self) -> &Arc<Mutex<EngineState>> {
+        &self.state
+    }
+
+    /// Return the name of each task waiting for a later cycle, along with 
+    /// the cycle it is waiting for.
+    pub fn sleeping_tasks(&self) -> Vec<(String, usize)> {
+        let state = self.state.lock().unwrap();
+        let waits = state.waits.borrow();
+        self.sleeping.values().map(|task| {
+            (task.name.to_string(), waits.sleeping[&task.id])
+        }).collect()
+    }
+
+    /// Return the name of each blocked task, along with the names of the 
+    /// wires it is blocked on. 
+    pub fn blocked_tasks(&self) -> Vec<(String, Vec<String>)> {
+        let state = self.state.lock().unwrap();
+        let waits = state.waits.borrow();
+        self.blocked.values().map(|task| {
+            let wires = waits.blocked.get(&task.id).into_iter().flatten()
+                .map(|id| match state.wires.net(*id).into_iter()
+                    .find_map(|id| state.wires.name(id)) 
+                {
+                    Some(name) => name.to_string(),
+                    None => format!("wire{}", id),
+                }).collect();
+            (task.name.to_string(), wires)
+        }).collect()
+    }
+
+    /// Attach an observer that receives [`EngineEvent`]s. 
+    ///
+    /// Pass an `Rc<RefCell<O>>` to keep a handle to the observer. 
+    pub fn add_observer(&mut self, observer: impl EngineObserver + 'a) {
+        self.observers.push(Box::new(observer));
+        self.update_tracing();
+    }
+
+    /// Record the events that observers want from [`EngineState`]. 
+    ///
+    /// This happens at the start of every cycle, so observers can change 
+    /// their filters between cycles. 
+    fn update_tracing(&mut self) {
+        let trace_wires = self.wants(EventKind::WireDriven);
+        let trace_forced = self.wants(EventKind::DriveIgnored);
+        let mut state = self.state.lock().unwrap();
+        state.trace_wires = trace_wires;
+        state.trace_forced = trace_forced;
+    }
+
+    /// Returns 'true' if any observer wants events of the given kind.
+    fn wants(&self, kind: EventKind) -> bool {
+        self.observers.iter().any(|o| o.wants(kind))
+    }
+
+    /// Send an event to all observers that want it. 
+    ///
+    /// The event is only constructed when some observer wants it.
+    fn emit(&mut self, kind: EventKind, f: impl FnOnce() -> EngineEvent) {
+        if !self.wants(kind) {
+            return;
+        }
+        let event = f();
+        for o in self.observers.iter_mut().filter(|o| o.wants(kind)) {
+            o.event(&event);
+        }
+    }
+
+    /// Deliver events recorded by [`EngineState`] to observers.
+    fn deliver_wire_events(&mut self) {
+        let events = {
+            let state = self.state.lock().unwrap();
+            std::mem::take(&mut *state.events.borrow_mut())
+        };
+        for event in events {
+            self.emit(event.kind(), || event);
+        }
+    }
+
+    /// Return the number of elapsed clock cycles.
+    pub fn cycles(&self) -> usize {
+        self.state.lock().unwrap().cycle
+    }
+
+    /// Reset the state of all wires, after transparent latches have held 
+    /// the values on their data wires.
     pub fn reset_wires(&self) {
-        self.state.lock().unwrap().wires.reset();
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        state.latches.capture(&state.wires);
+        state.wires.reset();
+        state.wire_domains.borrow_mut().clear();
     }
 
     /// Update the state of all registers.
@@ -214,11 +1158,177 @@ impl <'a> Engine<'a> {
         self.state.lock().unwrap().registers.update();
     }
 
+    /// Perform a single simulated clock-cycle, and then update registers 
+    /// and reset wires for the next cycle. 
+    ///
+    /// Panics if the simulation stalls (see [`Engine::try_step`]). 
     pub fn step(&mut self) { 
-        self.run();
+        if let Err(e) = self.try_step() {
+            panic!("{}", e);
+        }
+    }
+
+    /// Perform a single simulated clock-cycle, and then update registers 
+    /// and reset wires for the next cycle. 
+    ///
+    /// When any register commits on the falling edge (see 
+    /// [`ClockEdge`](crate::clock::ClockEdge)), the cycle is split in two 
+    /// halves. A single falling-edge register switches the whole engine to
+    /// this mode. 
+    ///
+    /// After the first half, falling-edge registers are updated and 
+    /// modules added with [`Engine::add_module`] are scheduled again for 
+    /// the second half. Wires and rising-edge registers driven by those 
+    /// modules are reset, while values driven by other tasks (which don't 
+    /// run again) are held until the end of the cycle. Checkers only run 
+    /// at the end of the cycle.
+    ///
+    /// Returns [`EngineErr::Stall`] (without ending the cycle) if only 
+    /// tasks blocked on wires remain. 
+    pub fn try_step(&mut self) -> Result<(), EngineErr> { 
+        let (start, half) = {
+            let state = self.state.lock().unwrap();
+            let start = state.phase == ClockPhase::High;
+            (start, start && state.registers.has_negedge())
+        };
+        if start {
+            self.update_tracing();
+        }
+        if half {
+            self.try_run()?;
+            let cycle = self.cycles();
+            if let Some(lint) = &mut self.lint {
+                lint.observe_half(&self.state.lock().unwrap().wires);
+            }
+            let written = {
+                let mut state = self.state.lock().unwrap();
+                let state = &mut *state;
+                // Values driven by tasks which don't run again are held
+                let held = state.held_wires.take();
+                state.latches.capture(&state.wires);
+                state.wires.reset_except(&held);
+                state.wire_domains.borrow_mut()
+                    .retain(|root, _| held.contains(root));
+                let edges: Vec<bool> = state.domains.iter()
+                    .map(|d| d.is_edge(cycle)).collect();
+                state.phase = ClockPhase::Low;
+                let held = state.held_registers.take();
+                state.registers.update_negedges(|d| edges[d.id()], &held)
+            };
+            self.emit_commits(cycle, written);
+            for (module, domain) in self.modules.clone() {
+                module.push_to(self, domain);
+            }
+        }
+        self.try_run()?;
+        let cycle = self.cycles();
+        if !self.checkers.is_empty() {
+            let state = self.state.lock().unwrap();
+            for c in self.checkers.iter_mut() {
+                c.check(cycle, &state);
+            }
+        }
+        if let Some(lint) = &mut self.lint {
+            let state = self.state.lock().unwrap();
+            lint.observe_wires(cycle, &state.wires);
+            lint.observe_registers(&state.registers);
+        }
+        self.state.lock().unwrap().crossings.borrow_mut().clear();
         self.reset_wires();
-        self.update_registers();
-        self.cycles += 1;
+        let written = {
+            let mut state = self.state.lock().unwrap();
+            let edges: Vec<bool> = state.domains.iter()
+                .map(|d| d.is_edge(cycle)).collect();
+            state.phase = ClockPhase::High;
+            state.registers.update_edges(|d| edges[d.id()])
+        };
+        self.emit_commits(cycle, written);
+        if let Some(lint) = &mut self.lint {
+            lint.observe_registers(&self.state.lock().unwrap().registers);
+        }
+        self.emit(EventKind::CycleEnd, || EngineEvent::CycleEnd { cycle });
+        self.state.lock().unwrap().cycle += 1;
+        self.wake_sleeping();
+        for (module, domain) in self.modules.clone() {
+            module.push_to(self, domain);
+        }
+        Ok(())
+    }
+
+    fn emit_commits(&mut self, cycle: usize, written: Vec<usize>) {
+        if !self.wants(EventKind::RegisterCommitted) {
+            return;
+        }
+        for id in written {
+            let event = {
+                let state = self.state.lock().unwrap();
+                EngineEvent::RegisterCommitted {
+                    cycle,
+                    id,
+                    name: state.registers.name(id).map(|s| s.to_string()),
+                    value: state.registers.fmt_register(id),
+                }
+            };
+            self.emit(EventKind::RegisterCommitted, || event);
+        }
+    }
+
+    /// Simulate `n` cycles. 
+    ///
+    /// Returns the number of cycles simulated, or the first error (see 
+    /// [`Engine::try_step`]). 
+    pub fn run_cycles(&mut self, n: usize) -> Result<usize, EngineErr> {
+        for _ in 0..n {
+            self.try_step()?;
+        }
+        Ok(n)
+    }
+
+    /// Simulate cycles until `pred` holds at the start of a cycle (after 
+    /// registers have been updated).
+    ///
+    /// Returns the number of cycles simulated, or [`EngineErr::Timeout`] if
+    /// `pred` does not hold within the cycle budget (see 
+    /// [`Engine::set_cycle_budget`]). 
+    pub fn run_until(&mut self, pred: impl Fn(&EngineState) -> bool) 
+        -> Result<usize, EngineErr> 
+    {
+        let mut taken = 0;
+        while !pred(&self.state.lock().unwrap()) {
+            if taken == self.cycle_budget {
+                return Err(EngineErr::Timeout { 
+                    cycle: self.cycles(), budget: self.cycle_budget 
+                });
+            }
+            self.try_step()?;
+            taken += 1;
+        }
+        Ok(taken)
+    }
+
+    /// Set the maximum number of cycles simulated by [`Engine::run_until`].
+    pub fn set_cycle_budget(&mut self, budget: usize) {
+        self.cycle_budget = budget;
+    }
+
+    /// Enable the [`Lint`] pass. 
+    ///
+    /// After this, each call to [`Engine::step`] records which wires were
+    /// driven/sampled and which registers changed during the cycle.
+    pub fn enable_lint(&mut self) {
+        self.lint.get_or_insert_with(Lint::new);
+    }
+
+    /// Return the [`Lint`] pass (if enabled).
+    pub fn lint(&self) -> Option<&Lint> {
+        self.lint.as_ref()
+    }
+
+    /// Return a [`LintReport`] aggregated over all cycles since the lint 
+    /// pass was enabled.
+    pub fn lint_report(&self) -> Option<LintReport> {
+        let state = self.state.lock().unwrap();
+        self.lint.as_ref().map(|l| l.report(&state.wires, &state.registers))
     }
 
 
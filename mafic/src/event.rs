@@ -0,0 +1,174 @@
+//! Events emitted by an [`Engine`](crate::engine::Engine) during simulation.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// The different kinds of [`EngineEvent`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum EventKind {
+    TaskPolled,
+    TaskPending,
+    TaskCompleted,
+    WireDriven,
+    RegisterCommitted,
+    DriveIgnored,
+    CycleEnd,
+}
+
+/// An event that occurred during simulation.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum EngineEvent {
+    /// A task is about to be polled
+    TaskPolled { cycle: usize, id: usize, name: Cow<'static, str> },
+
+    /// A task was polled and is still waiting to complete
+    TaskPending { cycle: usize, id: usize, name: Cow<'static, str> },
+
+    /// A task was polled and has completed
+    TaskCompleted { cycle: usize, id: usize, name: Cow<'static, str> },
+
+    /// A value was driven on a wire
+    WireDriven {
+        cycle: usize,
+        id: usize,
+        name: Option<String>,
+        /// The value (formatted with [`std::fmt::Debug`])
+        value: String
+    },
+
+    /// A new value was committed to a register
+    RegisterCommitted {
+        cycle: usize,
+        id: usize,
+        name: Option<String>,
+        /// The value (formatted with [`std::fmt::Debug`])
+        value: String
+    },
+
+    /// A value driven on a forced wire/register was ignored
+    DriveIgnored {
+        cycle: usize,
+        /// The name of the wire/register (ie. `wire{id}` or `reg{id}`
+        /// when unnamed)
+        signal: String,
+        /// The value (formatted with [`std::fmt::Debug`])
+        value: String
+    },
+
+    /// A clock cycle has ended
+    CycleEnd { cycle: usize },
+}
+impl EngineEvent {
+    /// Return the kind of this event.
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Self::TaskPolled { .. } => EventKind::TaskPolled,
+            Self::TaskPending { .. } => EventKind::TaskPending,
+            Self::TaskCompleted { .. } => EventKind::TaskCompleted,
+            Self::WireDriven { .. } => EventKind::WireDriven,
+            Self::RegisterCommitted { .. } => EventKind::RegisterCommitted,
+            Self::DriveIgnored { .. } => EventKind::DriveIgnored,
+            Self::CycleEnd { .. } => EventKind::CycleEnd,
+        }
+    }
+
+    /// Return the cycle in which this event occurred.
+    pub fn cycle(&self) -> usize {
+        match self {
+            Self::TaskPolled { cycle, .. }
+            | Self::TaskPending { cycle, .. }
+            | Self::TaskCompleted { cycle, .. }
+            | Self::WireDriven { cycle, .. }
+            | Self::RegisterCommitted { cycle, .. }
+            | Self::DriveIgnored { cycle, .. }
+            | Self::CycleEnd { cycle } => *cycle,
+        }
+    }
+}
+
+/// Trait implemented on types that receive [`EngineEvent`]s.
+///
+/// Observers are attached with
+/// [`Engine::add_observer`](crate::engine::Engine::add_observer).
+/// Events are only generated when at least one observer wants them, so an
+/// engine without observers does not pay for formatting values.
+pub trait EngineObserver {
+    /// Returns 'true' if this observer wants events of the given kind.
+    ///
+    /// Filters may change between cycles, but changes made during a cycle 
+    /// may not take effect until the next cycle. 
+    fn wants(&self, _kind: EventKind) -> bool { true }
+
+    /// Handle an event.
+    fn event(&mut self, event: &EngineEvent);
+}
+
+/// Allows the user to keep a handle to an observer owned by an engine.
+impl <O: EngineObserver> EngineObserver for Rc<RefCell<O>> {
+    fn wants(&self, kind: EventKind) -> bool {
+        self.borrow().wants(kind)
+    }
+    fn event(&mut self, event: &EngineEvent) {
+        self.borrow_mut().event(event)
+    }
+}
+
+/// An observer that prints events to stdout.
+pub struct PrintObserver {
+    /// The kinds of events to print
+    pub kinds: Vec<EventKind>,
+}
+impl PrintObserver {
+    /// Print all events.
+    pub fn all() -> Self {
+        Self { kinds: vec![
+            EventKind::TaskPolled,
+            EventKind::TaskPending,
+            EventKind::TaskCompleted,
+            EventKind::WireDriven,
+            EventKind::RegisterCommitted,
+            EventKind::DriveIgnored,
+            EventKind::CycleEnd,
+        ] }
+    }
+    /// Print only the given kinds of events.
+    pub fn only(kinds: &[EventKind]) -> Self {
+        Self { kinds: kinds.to_vec() }
+    }
+}
+impl EngineObserver for PrintObserver {
+    fn wants(&self, kind: EventKind) -> bool {
+        self.kinds.contains(&kind)
+    }
+    fn event(&mut self, event: &EngineEvent) {
+        println!("{:?}", event);
+    }
+}
+
+/// An observer that records events.
+#[derive(Default)]
+pub struct EventLog {
+    /// The kinds of events to record (or all events when `None`)
+    pub kinds: Option<Vec<EventKind>>,
+
+    /// Recorded events
+    pub events: Vec<EngineEvent>,
+}
+impl EventLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Record only the given kinds of events.
+    pub fn only(kinds: &[EventKind]) -> Self {
+        Self { kinds: Some(kinds.to_vec()), events: Vec::new() }
+    }
+}
+impl EngineObserver for EventLog {
+    fn wants(&self, kind: EventKind) -> bool {
+        self.kinds.as_ref().is_none_or(|k| k.contains(&kind))
+    }
+    fn event(&mut self, event: &EngineEvent) {
+        self.events.push(event.clone());
+    }
+}
@@ -0,0 +1,150 @@
+//! Synchronous FIFO queues.
+
+use std::cell::RefCell;
+
+use crate::decoupled::Decoupled;
+use crate::engine::{ EngineState, current_cycle };
+use crate::module::{InstanceId, ModuleLike};
+use crate::register::RegisterId;
+use crate::wire::WireId;
+
+/// A synchronous FIFO queue with `DEPTH` entries.
+///
+/// Values are accepted on `enq` and removed on `deq`. The `full`, `empty`
+/// and `count` outputs describe the occupancy at the start of each cycle.
+///
+/// By default, a value accepted on `enq` can be removed from `deq` in the
+/// following cycle, and `enq` is not ready while the queue is full. This
+/// can be changed with:
+///
+/// - [`Fifo::with_flow`]: when the queue is empty, a value on `enq` can
+///   pass through to `deq` in the same cycle
+/// - [`Fifo::with_pipe`]: when the queue is full, `enq` is ready if a value
+///   is being removed from `deq` in the same cycle
+///
+pub struct Fifo<T: Copy + std::fmt::Debug + 'static, const DEPTH: usize> {
+    pub enq: Decoupled<T>,
+    pub deq: Decoupled<T>,
+    pub full: WireId<bool>,
+    pub empty: WireId<bool>,
+    pub count: WireId<usize>,
+
+    flow: bool,
+    pipe: bool,
+
+    /// Number of cycles spent at each occupancy (if enabled), and the 
+    /// last cycle which was counted
+    histogram: Option<RefCell<(Vec<usize>, Option<usize>)>>,
+
+    data: [RegisterId<T>; DEPTH],
+    head: RegisterId<usize>,
+    tail: RegisterId<usize>,
+    occupancy: RegisterId<usize>,
+    instance: InstanceId,
+}
+impl <T: Copy + std::fmt::Debug + 'static, const DEPTH: usize> Fifo<T, DEPTH> {
+    /// Allow values to pass through an empty queue in a single cycle.
+    pub fn with_flow(mut self, flow: bool) -> Self {
+        self.flow = flow;
+        self
+    }
+
+    /// Allow values to be accepted by a full queue while a value is being
+    /// removed in the same cycle.
+    pub fn with_pipe(mut self, pipe: bool) -> Self {
+        self.pipe = pipe;
+        self
+    }
+
+    /// Record the number of cycles spent at each occupancy.
+    pub fn with_histogram(mut self) -> Self {
+        self.histogram = Some(RefCell::new((vec![0; DEPTH + 1], None)));
+        self
+    }
+
+    /// Return the number of cycles spent at each occupancy (indexed by the
+    /// number of entries), if enabled with [`Fifo::with_histogram`].
+    pub fn histogram(&self) -> Option<Vec<usize>> {
+        self.histogram.as_ref().map(|h| h.borrow().0.clone())
+    }
+}
+impl <T, const DEPTH: usize> ModuleLike for Fifo<T, DEPTH>
+    where T: Copy + Default + std::fmt::Debug + 'static
+{
+    fn new_instance(state: &mut EngineState) -> Self {
+        assert!(DEPTH > 0, "Fifo must have at least one entry");
+        Self {
+            enq: Decoupled::new(&mut state.wires),
+            deq: Decoupled::new(&mut state.wires),
+            full: state.wires.alloc(),
+            empty: state.wires.alloc(),
+            count: state.wires.alloc(),
+            flow: false,
+            pipe: false,
+            histogram: None,
+            data: std::array::from_fn(|_| state.registers.alloc(T::default())),
+            head: state.registers.alloc(0),
+            tail: state.registers.alloc(0),
+            occupancy: state.registers.alloc(0),
+            instance: state.alloc_instance::<Self>(),
+        }
+    }
+    fn instance(&self) -> Option<InstanceId> { Some(self.instance) }
+
+    async fn run(&self) {
+        let count = self.occupancy.sample().await;
+        let head = self.head.sample().await;
+        let tail = self.tail.sample().await;
+        if let Some(h) = &self.histogram {
+            // Count each cycle once, even when this runs in both halves of
+            // a cycle (see [`crate::clock::ClockEdge`])
+            let cycle = current_cycle().await;
+            let (hist, last) = &mut *h.borrow_mut();
+            if last.replace(cycle) != Some(cycle) {
+                hist[count] += 1;
+            }
+        }
+        let empty = count == 0;
+        let full = count == DEPTH;
+        self.full.drive(full).await;
+        self.empty.drive(empty).await;
+        self.count.drive(count).await;
+
+        // Output the oldest entry (or the incoming value when bypassing)
+        let bypass = self.flow && empty;
+        let deq_valid = if bypass {
+            let valid = self.enq.valid.sample().await;
+            if valid {
+                self.deq.data.drive(self.enq.data.sample().await).await;
+            }
+            valid
+        } else {
+            if !empty {
+                self.deq.data.drive(self.data[head].sample().await).await;
+            }
+            !empty
+        };
+        self.deq.valid.drive(deq_valid).await;
+
+        let deq_ready = self.deq.ready.sample().await;
+        let enq_ready = !full || (self.pipe && deq_ready);
+        self.enq.ready.drive(enq_ready).await;
+
+        let enq_fire = enq_ready && self.enq.valid.sample().await;
+        let deq_fire = deq_valid && deq_ready;
+        if bypass && enq_fire && deq_fire {
+            return;
+        }
+        if enq_fire {
+            self.data[tail].drive(self.enq.data.sample().await).await;
+            self.tail.drive((tail + 1) % DEPTH).await;
+        }
+        if deq_fire {
+            self.head.drive((head + 1) % DEPTH).await;
+        }
+        if enq_fire != deq_fire {
+            let next = if enq_fire { count + 1 } else { count - 1 };
+            self.occupancy.drive(next).await;
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Types for representing simulated level-sensitive latches.
+//!
+//! A latch is transparent while its enable wire is high: reading the latch
+//! waits for the data wire to be driven, and returns the same value. When
+//! the enable wire is low, reading the latch returns the value held from
+//! the last cycle in which it was transparent.
+//!
+//! The enable and data wires are ordinary wires, so a latch whose data
+//! depends on its own output while transparent is a combinational loop,
+//! and stalls the simulation like any other (see
+//! [`EngineErr::Stall`](crate::engine::EngineErr::Stall)).
+
+use std::collections::*;
+use std::rc::*;
+use std::cell::*;
+use std::marker::PhantomData;
+use std::future::Future;
+use std::task::{ Context, Poll };
+use std::pin::Pin;
+use std::sync::{ Arc, Mutex };
+use std::any::*;
+
+use crate::engine::EngineState;
+use crate::wire::{ WireId, WireMap };
+
+
+/// A token for a simulated latch whose state is tracked by [`EngineState`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LatchId<T> {
+    _t: PhantomData<T>,
+    id: usize,
+    enable: WireId<bool>,
+    input: WireId<T>,
+}
+impl <T: Copy + std::fmt::Debug + 'static> LatchId<T> {
+    pub fn id(&self) -> usize { self.id }
+
+    /// The wire which makes this latch transparent when high
+    pub fn enable(&self) -> WireId<bool> { self.enable }
+
+    /// The data wire
+    pub fn input(&self) -> WireId<T> { self.input }
+
+    /// Sample the output of this latch
+    pub async fn sample(&self) -> T {
+        LatchFuture { latch: *self }.await
+    }
+    /// Drive the data wire of this latch
+    pub async fn drive(&self, data: T) {
+        self.input.drive(data).await
+    }
+    /// Drive the enable wire of this latch
+    pub async fn set_enable(&self, enable: bool) {
+        self.enable.drive(enable).await
+    }
+}
+
+/// Future representing a read from a simulated latch.
+///
+/// Waits for the enable wire, and then (when transparent) for the data
+/// wire.
+pub struct LatchFuture<T> {
+    latch: LatchId<T>,
+}
+impl <T> Future for LatchFuture<T>
+where T: Copy + std::fmt::Debug + 'static
+{
+    type Output = T;
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let state: &mut Arc<Mutex<EngineState>> = ctx.ext().downcast_mut().unwrap();
+        let state = state.lock().unwrap();
+
+        let wire = match state.read_wire(self.latch.enable) {
+            None => self.latch.enable.id(),
+            Some(false) => return Poll::Ready(state.latches.peek_latch(self.latch)),
+            Some(true) => match state.read_wire(self.latch.input) {
+                Some(data) => return Poll::Ready(data),
+                None => self.latch.input.id(),
+            },
+        };
+        state.wait_on_wire(wire);
+        Poll::Pending
+    }
+}
+
+
+/// The simulated state of a latch tracked by [`Engine`](crate::engine::Engine).
+#[derive(Debug)]
+pub struct LatchState<T: std::fmt::Debug> {
+    /// The value held by this latch
+    pub data: T,
+    /// The value held by this latch on reset
+    pub reset_data: T,
+    enable: WireId<bool>,
+    input: WireId<T>,
+}
+impl <T: Copy + std::fmt::Debug + 'static> LatchLike for LatchState<T> {
+    fn reset(&mut self) {
+        self.data = self.reset_data;
+    }
+    fn capture(&mut self, wires: &WireMap) -> bool {
+        if wires.peek_wire(self.enable) != Some(true) {
+            return false;
+        }
+        match wires.peek_wire(self.input) {
+            Some(data) => { self.data = data; true },
+            None => false,
+        }
+    }
+    fn data_debug(&self) -> &dyn std::fmt::Debug { &self.data }
+    fn as_any(&self) -> &dyn Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn Any { self }
+}
+
+/// Trait implemented on types that represent the simulated state of a latch.
+pub trait LatchLike {
+    fn reset(&mut self);
+    /// Hold the value on the data wire if the latch is transparent,
+    /// returning 'true' if a value was captured
+    fn capture(&mut self, wires: &WireMap) -> bool;
+    /// Return a type-erased reference to the held value
+    fn data_debug(&self) -> &dyn std::fmt::Debug;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+pub struct LatchMap {
+    /// Type-erased container for [LatchState]
+    data: BTreeMap<usize, Rc<RefCell<Box<dyn LatchLike>>>>,
+
+    /// Human-readable names for latches
+    pub names: BTreeMap<usize, String>,
+
+    next_sid: usize,
+}
+impl Default for LatchMap {
+    fn default() -> Self { Self::new() }
+}
+impl LatchMap {
+    pub fn new() -> Self {
+        Self {
+            data: BTreeMap::new(),
+            names: BTreeMap::new(),
+            next_sid: 1,
+        }
+    }
+
+    /// Allocate a latch with the given enable and data wires, holding
+    /// `init` until it first becomes transparent.
+    pub fn alloc<T: Copy + std::fmt::Debug + 'static>(&mut self,
+        enable: WireId<bool>, input: WireId<T>, init: T) -> LatchId<T>
+    {
+        let id = self.next_sid;
+        self.data.insert(id,
+            Rc::new(RefCell::new(Box::new(LatchState::<T> {
+                data: init,
+                reset_data: init,
+                enable,
+                input,
+            })))
+        );
+        self.next_sid += 1;
+        LatchId { _t: PhantomData, id, enable, input }
+    }
+
+    /// Allocate a latch with a human-readable name.
+    pub fn alloc_named<T: Copy + std::fmt::Debug + 'static>(&mut self,
+        name: impl Into<String>, enable: WireId<bool>, input: WireId<T>,
+        init: T) -> LatchId<T>
+    {
+        let res = self.alloc(enable, input, init);
+        self.names.insert(res.id, name.into());
+        res
+    }
+
+    /// Return the human-readable name of a latch (if it has one).
+    pub fn name(&self, id: usize) -> Option<&str> {
+        self.names.get(&id).map(|s| s.as_str())
+    }
+
+    /// Return the identifiers for all tracked latches.
+    pub fn ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.data.keys().copied()
+    }
+
+    /// Return the type-erased state of a latch.
+    pub(crate) fn get(&self, id: usize) -> Option<&Rc<RefCell<Box<dyn LatchLike>>>> {
+        self.data.get(&id)
+    }
+
+    /// Return the value held by a latch.
+    pub fn peek_latch<T: Copy + std::fmt::Debug + 'static>
+        (&self, latch: LatchId<T>) -> T
+    {
+        let s = self.data.get(&latch.id).unwrap().borrow();
+        s.as_any().downcast_ref::<LatchState<T>>().unwrap().data
+    }
+
+    /// Reset all of the latches to their reset values.
+    pub fn reset(&mut self) {
+        for item in &self.data {
+            item.1.borrow_mut().reset();
+        }
+    }
+
+    /// Hold the values driven on the data wires of all transparent latches.
+    ///
+    /// This must happen before the wires are reset. Returns the identifiers
+    /// of all latches which captured a value.
+    pub fn capture(&mut self, wires: &WireMap) -> Vec<usize> {
+        let mut written = Vec::new();
+        for item in &self.data {
+            if item.1.borrow_mut().capture(wires) {
+                written.push(*item.0);
+            }
+        }
+        written
+    }
+
+    /// Format the value held by a latch with [`std::fmt::Debug`].
+    pub fn fmt_latch(&self, id: usize) -> String {
+        format!("{:?}", self.data.get(&id).unwrap().borrow().data_debug())
+    }
+}
@@ -1,22 +1,65 @@
 
 #![feature(context_ext)]
 #![feature(local_waker)]
-#![feature(noop_waker)]
-#![feature(downcast_unchecked)]
 
 #![doc = include_str!("../README.md")]
 
+// The engine is single-threaded by design; state is shared with tasks through
+// the context extension rather than across threads. 
+#![allow(clippy::arc_with_non_send_sync)]
+#![allow(async_fn_in_trait)]
+
 pub mod wire; 
 pub mod register;
+pub mod latch;
 pub mod engine;
 pub mod module;
+pub mod lint;
+pub mod event;
+pub mod combinator;
+pub mod bits;
+pub mod logic;
+pub mod check;
+pub mod bundle;
+pub mod decoupled;
+pub mod fifo;
+pub mod clock;
+pub mod cdc;
+pub mod testbench;
+pub mod scoreboard;
+pub mod assertion;
+pub mod coverage;
+pub mod random;
+pub mod order;
+pub mod debug;
+
+// Lets derived implementations refer to `::mafic` from within this crate
+extern crate self as mafic;
 
 use std::sync::*;
 
-pub use crate::engine::{Engine, EngineState};
+pub use crate::engine::{Engine, EngineErr, EngineState, next_cycle, current_cycle, delay, wait_until_cycle};
 pub use crate::wire::{WireId, WireMap, WireState};
 pub use crate::register::{RegisterId, RegisterMap, RegisterState};
-pub use crate::module::ModuleLike;
+pub use crate::latch::{LatchId, LatchMap, LatchState};
+pub use crate::module::{ ModuleLike, InstanceId, Processes };
+pub use crate::lint::{ Lint, LintReport };
+pub use crate::event::{ EngineEvent, EngineObserver, EventKind };
+pub use crate::combinator::{ join, join_all, select, sample_all, Either };
+pub use crate::bits::{ Bits, SInt, BitWidth };
+pub use crate::logic::{ FourState, Logic, LogicVec };
+pub use crate::check::{ Checker, XChecker, XViolation };
+pub use crate::bundle::Bundle;
+pub use mafic_derive::Bundle;
+pub use crate::decoupled::{ Decoupled, Producer, Consumer, DecoupledChecker };
+pub use crate::fifo::Fifo;
+pub use crate::clock::{ ClockDomain, ClockEdge, ClockPhase, DomainId, clock_phase };
+pub use crate::testbench::{ Driver, Monitor, Signals, Testbench };
+pub use crate::scoreboard::{ Scoreboard, ScoreboardReport, MatchOrder };
+pub use crate::assertion::{ Assertions, AssertionFailure, Cond };
+pub use crate::coverage::{ Coverage, CoverageReport, Coverpoint };
+pub use crate::random::{ Random, Rng, drive_random };
+pub use crate::debug::Debugger;
 
 thread_local! { 
     /// The global instance of [`EngineState`] managed by the library. 
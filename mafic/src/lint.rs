@@ -0,0 +1,185 @@
+//! An [opt-in] lint pass for finding hookup mistakes in a model.
+
+use std::collections::*;
+use std::fmt;
+
+use crate::wire::WireMap;
+use crate::register::RegisterMap;
+
+/// A list of potential hookup mistakes found by [`Lint`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LintReport {
+    /// The cycle associated with this report.
+    ///
+    /// When `None`, this report has been aggregated over all cycles.
+    pub cycle: Option<usize>,
+
+    /// Names of wires which were never driven
+    pub undriven: Vec<String>,
+
+    /// Names of wires which were driven, but never sampled
+    pub unread: Vec<String>,
+
+    /// Names of registers whose value never changed
+    pub unchanged: Vec<String>,
+}
+impl LintReport {
+    /// Returns 'true' if no problems were found.
+    pub fn is_clean(&self) -> bool {
+        self.undriven.is_empty()
+            && self.unread.is_empty()
+            && self.unchanged.is_empty()
+    }
+}
+impl fmt::Display for LintReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.cycle {
+            Some(cycle) => writeln!(f, "lint report (cycle {})", cycle)?,
+            None => writeln!(f, "lint report")?,
+        }
+        for name in &self.undriven {
+            writeln!(f, "  undriven wire: {}", name)?;
+        }
+        for name in &self.unread {
+            writeln!(f, "  unread wire: {}", name)?;
+        }
+        for name in &self.unchanged {
+            writeln!(f, "  unchanged register: {}", name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Tracks the usage of wires and registers over a simulation.
+///
+/// When enabled with [`Engine::enable_lint`](crate::engine::Engine::enable_lint),
+/// the engine passes the state of all wires to the lint pass at the end of
+/// each cycle (before wires are reset), and the state of all registers both
+/// before and after registers are updated.
+///
+/// Wires marked with [`WireMap::set_optional`] are never reported.
+#[derive(Default)]
+pub struct Lint {
+    /// Number of cycles observed
+    cycles: usize,
+
+    /// Wires which have been driven at least once
+    driven: BTreeSet<usize>,
+
+    /// Wires which have been sampled at least once
+    sampled: BTreeSet<usize>,
+
+    /// The initial value of each register (formatted with [`fmt::Debug`])
+    initial: BTreeMap<usize, String>,
+
+    /// Registers which have changed at least once
+    changed: BTreeSet<usize>,
+
+    /// The report for the most-recently observed cycle
+    last: Option<LintReport>,
+
+    /// Wires driven during the first half of the current cycle
+    half_driven: BTreeSet<usize>,
+
+    /// Wires sampled during the first half of the current cycle
+    half_sampled: BTreeSet<usize>,
+}
+impl Lint {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the number of cycles observed by this lint pass.
+    pub fn cycles(&self) -> usize { self.cycles }
+
+    /// Observe the state of all wires at the end of the first half of a 
+    /// cycle (see [`Engine::try_step`](crate::engine::Engine::try_step)).
+    ///
+    /// Both halves are reported as a single cycle by the next call to 
+    /// [`Lint::observe_wires`].
+    pub fn observe_half(&mut self, wires: &WireMap) {
+        for (id, s) in &wires.data {
+            let s = s.borrow();
+            if s.is_driven() { self.half_driven.insert(*id); }
+            if s.is_sampled() { self.half_sampled.insert(*id); }
+        }
+    }
+
+    /// Observe the state of all wires at the end of a cycle.
+    pub fn observe_wires(&mut self, cycle: usize, wires: &WireMap) {
+        let mut report = LintReport { cycle: Some(cycle), ..Default::default() };
+        for (id, s) in &wires.data {
+            let s = s.borrow();
+            let driven = s.is_driven() || self.half_driven.contains(id);
+            let sampled = s.is_sampled() || self.half_sampled.contains(id);
+            if driven { self.driven.insert(*id); }
+            if sampled { self.sampled.insert(*id); }
+            if wires.optional.contains(id) {
+                continue;
+            }
+            if !driven {
+                report.undriven.push(Self::wire_name(wires, *id));
+            } else if !sampled {
+                report.unread.push(Self::wire_name(wires, *id));
+            }
+        }
+        self.last = Some(report);
+        self.half_driven.clear();
+        self.half_sampled.clear();
+        self.cycles += 1;
+    }
+
+    /// Observe the state of all registers.
+    ///
+    /// Registers are compared against the value observed on the first call.
+    pub fn observe_registers(&mut self, registers: &RegisterMap) {
+        for id in registers.ids() {
+            if self.changed.contains(&id) {
+                continue;
+            }
+            let value = registers.fmt_register(id);
+            match self.initial.get(&id) {
+                Some(initial) if *initial != value => { 
+                    self.changed.insert(id); 
+                },
+                Some(_) => {},
+                None => { self.initial.insert(id, value); },
+            }
+        }
+    }
+
+    /// Return the report for the most-recently observed cycle.
+    pub fn cycle_report(&self) -> Option<&LintReport> {
+        self.last.as_ref()
+    }
+
+    /// Return a report aggregated over all observed cycles.
+    pub fn report(&self, wires: &WireMap, registers: &RegisterMap)
+        -> LintReport
+    {
+        let mut report = LintReport::default();
+        for id in wires.data.keys() {
+            if wires.optional.contains(id) {
+                continue;
+            }
+            if !self.driven.contains(id) {
+                report.undriven.push(Self::wire_name(wires, *id));
+            } else if !self.sampled.contains(id) {
+                report.unread.push(Self::wire_name(wires, *id));
+            }
+        }
+        for id in registers.ids() {
+            if !self.changed.contains(&id) {
+                let name = registers.name(id).map(|s| s.to_string())
+                    .unwrap_or_else(|| format!("reg{}", id));
+                report.unchanged.push(name);
+            }
+        }
+        report
+    }
+
+    fn wire_name(wires: &WireMap, id: usize) -> String {
+        wires.name(id).map(|s| s.to_string())
+            .unwrap_or_else(|| format!("wire{}", id))
+    }
+}
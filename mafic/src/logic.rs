@@ -0,0 +1,272 @@
+//! Four-state (0/1/X/Z) logic values.
+//!
+//! [`Logic`] is a single four-state bit, and [`LogicVec<N>`] is an `N`-bit
+//! four-state vector. Unknown (X) and high-impedance (Z) bits propagate
+//! through logic and arithmetic as X, so a missing reset or an undriven
+//! input becomes visible on the outputs that depend on it.
+//!
+//! These types implement [`FourState`], which lets the engine use them in
+//! four-state simulation (see
+//! [`EngineState::set_four_state`](crate::engine::EngineState::set_four_state)):
+//!
+//! - Registers allocated with
+//!   [`RegisterMap::alloc_four_state`](crate::register::RegisterMap::alloc_four_state)
+//!   power up as X until they are reset
+//!
+//! - Wires allocated with
+//!   [`WireMap::alloc_four_state`](crate::wire::WireMap::alloc_four_state)
+//!   read as Z (instead of blocking forever) when no process drives them
+
+use std::fmt;
+use std::ops::*;
+
+use crate::bits::Bits;
+
+/// Trait implemented on types with unknown (X) and high-impedance (Z) values.
+pub trait FourState: Copy {
+    /// The value with all bits unknown
+    fn x() -> Self;
+
+    /// The value with all bits high-impedance
+    fn z() -> Self;
+
+    /// Returns 'true' if any bit is unknown or high-impedance.
+    fn is_unknown(&self) -> bool;
+}
+
+/// A single four-state bit.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Logic {
+    #[default]
+    Zero,
+    One,
+    X,
+    Z,
+}
+impl Logic {
+    /// Return the value as a `bool`, or `None` if the value is unknown.
+    pub fn to_bool(self) -> Option<bool> {
+        match self {
+            Self::Zero => Some(false),
+            Self::One => Some(true),
+            _ => None,
+        }
+    }
+
+    /// Returns 'true' if the value is known to be `1`.
+    pub fn is_one(self) -> bool { self == Self::One }
+
+    /// Returns 'true' if the value is known to be `0`.
+    pub fn is_zero(self) -> bool { self == Self::Zero }
+}
+impl FourState for Logic {
+    fn x() -> Self { Self::X }
+    fn z() -> Self { Self::Z }
+    fn is_unknown(&self) -> bool { matches!(self, Self::X | Self::Z) }
+}
+impl From<bool> for Logic {
+    fn from(value: bool) -> Self {
+        if value { Self::One } else { Self::Zero }
+    }
+}
+impl BitAnd for Logic {
+    type Output = Self;
+    fn bitand(self, rhs: Self) -> Self {
+        match (self, rhs) {
+            (Self::Zero, _) | (_, Self::Zero) => Self::Zero,
+            (Self::One, Self::One) => Self::One,
+            _ => Self::X,
+        }
+    }
+}
+impl BitOr for Logic {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        match (self, rhs) {
+            (Self::One, _) | (_, Self::One) => Self::One,
+            (Self::Zero, Self::Zero) => Self::Zero,
+            _ => Self::X,
+        }
+    }
+}
+impl BitXor for Logic {
+    type Output = Self;
+    fn bitxor(self, rhs: Self) -> Self {
+        match (self.to_bool(), rhs.to_bool()) {
+            (Some(a), Some(b)) => Self::from(a ^ b),
+            _ => Self::X,
+        }
+    }
+}
+impl Not for Logic {
+    type Output = Self;
+    fn not(self) -> Self {
+        match self.to_bool() {
+            Some(a) => Self::from(!a),
+            None => Self::X,
+        }
+    }
+}
+impl fmt::Debug for Logic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = match self {
+            Self::Zero => '0', Self::One => '1', Self::X => 'x', Self::Z => 'z',
+        };
+        write!(f, "1'b{}", c)
+    }
+}
+
+/// An `N`-bit four-state vector.
+///
+/// Each bit is represented by a pair of bits in `value` and `unknown`:
+///
+/// | `unknown` | `value` | state |
+/// |-----------|---------|-------|
+/// | 0         | 0       | 0     |
+/// | 0         | 1       | 1     |
+/// | 1         | 0       | X     |
+/// | 1         | 1       | Z     |
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct LogicVec<const N: usize> {
+    value: u128,
+    unknown: u128,
+}
+impl <const N: usize> LogicVec<N> {
+    /// Create a new (fully-known) value, truncating `value` to `N` bits.
+    pub const fn new(value: u128) -> Self {
+        Self { value: value & Bits::<N>::MASK, unknown: 0 }
+    }
+
+    /// Return the value of bit `idx`.
+    pub fn bit(self, idx: usize) -> Logic {
+        assert!(idx < N, "bit {} out of range for LogicVec<{}>", idx, N);
+        match ((self.unknown >> idx) & 1, (self.value >> idx) & 1) {
+            (0, 0) => Logic::Zero,
+            (0, _) => Logic::One,
+            (_, 0) => Logic::X,
+            _ => Logic::Z,
+        }
+    }
+
+    /// Return a copy of this value with bit `idx` set to `value`.
+    pub fn with_bit(self, idx: usize, value: Logic) -> Self {
+        assert!(idx < N, "bit {} out of range for LogicVec<{}>", idx, N);
+        let (u, v) = match value {
+            Logic::Zero => (0, 0),
+            Logic::One => (0, 1),
+            Logic::X => (1, 0),
+            Logic::Z => (1, 1),
+        };
+        Self {
+            value: (self.value & !(1 << idx)) | (v << idx),
+            unknown: (self.unknown & !(1 << idx)) | (u << idx),
+        }
+    }
+
+    /// Return the value, or `None` if any bit is unknown.
+    pub fn to_bits(self) -> Option<Bits<N>> {
+        if self.unknown == 0 { Some(Bits::new(self.value)) } else { None }
+    }
+
+    /// Four-state equality: X when either value has unknown bits.
+    pub fn eq4(self, other: Self) -> Logic {
+        match (self.to_bits(), other.to_bits()) {
+            (Some(a), Some(b)) => Logic::from(a == b),
+            _ => Logic::X,
+        }
+    }
+
+    /// OR of all bits.
+    pub fn or_reduce(self) -> Logic {
+        (0..N).fold(Logic::Zero, |acc, i| acc | self.bit(i))
+    }
+
+    /// AND of all bits.
+    pub fn and_reduce(self) -> Logic {
+        (0..N).fold(Logic::One, |acc, i| acc & self.bit(i))
+    }
+
+    /// XOR of all bits.
+    pub fn xor_reduce(self) -> Logic {
+        (0..N).fold(Logic::Zero, |acc, i| acc ^ self.bit(i))
+    }
+
+    /// Returns a mask of the bits which are unknown after treating Z as X.
+    fn xmask(self) -> u128 { self.unknown }
+
+    /// Returns a mask of the bits which are known to be `1`.
+    fn ones(self) -> u128 { self.value & !self.unknown }
+
+    /// Returns a mask of the bits which are known to be `0`.
+    fn zeros(self) -> u128 { !self.value & !self.unknown & Bits::<N>::MASK }
+}
+impl <const N: usize> FourState for LogicVec<N> {
+    fn x() -> Self { Self { value: 0, unknown: Bits::<N>::MASK } }
+    fn z() -> Self { Self { value: Bits::<N>::MASK, unknown: Bits::<N>::MASK } }
+    fn is_unknown(&self) -> bool { self.unknown != 0 }
+}
+impl <const N: usize> From<Bits<N>> for LogicVec<N> {
+    fn from(value: Bits<N>) -> Self { Self::new(value.value()) }
+}
+
+impl <const N: usize> BitAnd for LogicVec<N> {
+    type Output = Self;
+    fn bitand(self, rhs: Self) -> Self {
+        let zeros = self.zeros() | rhs.zeros();
+        let ones = self.ones() & rhs.ones();
+        Self { value: ones, unknown: Bits::<N>::MASK & !(zeros | ones) }
+    }
+}
+impl <const N: usize> BitOr for LogicVec<N> {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        let ones = self.ones() | rhs.ones();
+        let zeros = self.zeros() & rhs.zeros();
+        Self { value: ones, unknown: Bits::<N>::MASK & !(zeros | ones) }
+    }
+}
+impl <const N: usize> BitXor for LogicVec<N> {
+    type Output = Self;
+    fn bitxor(self, rhs: Self) -> Self {
+        let unknown = self.xmask() | rhs.xmask();
+        Self { value: (self.value ^ rhs.value) & !unknown, unknown }
+    }
+}
+impl <const N: usize> Not for LogicVec<N> {
+    type Output = Self;
+    fn not(self) -> Self {
+        Self { value: !self.value & !self.unknown & Bits::<N>::MASK,
+            unknown: self.unknown }
+    }
+}
+
+// Arithmetic results are entirely unknown if any input bit is unknown
+macro_rules! impl_arith {
+    ($($Op:ident $op:ident),*) => { $(
+        impl <const N: usize> $Op for LogicVec<N> {
+            type Output = Self;
+            fn $op(self, rhs: Self) -> Self {
+                match (self.to_bits(), rhs.to_bits()) {
+                    (Some(a), Some(b)) => Self::from($Op::$op(a, b)),
+                    _ => Self::x(),
+                }
+            }
+        }
+    )* };
+}
+impl_arith!(Add add, Sub sub, Mul mul);
+
+impl <const N: usize> fmt::Debug for LogicVec<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}'b", N)?;
+        for i in (0..N).rev() {
+            let c = match self.bit(i) {
+                Logic::Zero => '0', Logic::One => '1',
+                Logic::X => 'x', Logic::Z => 'z',
+            };
+            write!(f, "{}", c)?;
+        }
+        Ok(())
+    }
+}
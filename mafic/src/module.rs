@@ -1,7 +1,10 @@
 //! Types for representing simulated components/modules. 
 
-use crate::wire::*;
-use crate::engine::{ Engine, EngineState };
+use std::borrow::Cow;
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::engine::EngineState;
 
 /// Trait implemented on types that represent a simulated "module".
 ///
@@ -21,7 +24,10 @@ use crate::engine::{ Engine, EngineState };
 ///   blocks until the wire has been driven by some other module being 
 ///   simulated concurrently. 
 ///
-///
+/// - A module may describe several independent processes (like `always` 
+///   blocks in Verilog) with [`ModuleLike::processes`]. Each process is 
+///   scheduled as a separate task, so a process blocked on some wire does 
+///   not prevent the other processes in the module from making progress. 
 ///
 pub trait ModuleLike { 
     /// Creates an instance this module. 
@@ -31,11 +37,47 @@ pub trait ModuleLike {
     /// this method when creating all submodules. 
     fn new_instance(e: &mut EngineState) -> Self;
 
+    /// Returns the name of this instance. 
+    ///
+    /// By default, instances are unnamed, and the [`Engine`] names them 
+    /// after their type (see [`Engine::schedule_module`]). Modules can 
+    /// override this to use a name chosen by the parent module. 
+    ///
+    /// [`Engine`]: crate::engine::Engine
+    /// [`Engine::schedule_module`]: crate::engine::Engine::schedule_module
+    fn name(&self) -> Option<String> { None }
+
+    /// Returns the identifier allocated for this instance with 
+    /// [`EngineState::alloc_instance`] in [`ModuleLike::new_instance`].
+    ///
+    /// The [`Engine`] names unnamed instances after this, so that names 
+    /// follow the order in which instances were built. Instances without 
+    /// an identifier are told apart by their address, and numbered in the
+    /// order they are first scheduled (so zero-sized modules, or modules 
+    /// moved to an address previously used by another instance, may share
+    /// a name). 
+    ///
+    /// [`Engine`]: crate::engine::Engine
+    fn instance(&self) -> Option<InstanceId> { None }
+
     /// Describes the simulated behavior for this module.
     /// 
     /// The future returned by this function is scheduled on an [`Engine`]. 
+    /// Modules which override [`ModuleLike::processes`] should still 
+    /// describe their complete behavior here (ie. by joining the 
+    /// processes), for use by parent modules. 
     async fn run(&self);
 
+    /// Returns the independent processes describing the simulated behavior
+    /// for this module. 
+    ///
+    /// Each process is scheduled on an [`Engine`] as a separate task. 
+    /// By default, this is a single unnamed process running 
+    /// [`ModuleLike::run`]. 
+    fn processes(&self) -> Processes<'_> {
+        Processes::new().with("", self.run())
+    }
+
 
     ///// [Asynchronously] sample a signal.
     //async fn sample<'a, T: Copy + std::fmt::Debug + 'static>
@@ -54,6 +96,67 @@ pub trait ModuleLike {
 }
 
 
+/// Identifies a module instance (see [`ModuleLike::instance`]). 
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct InstanceId(pub(crate) usize);
+impl InstanceId {
+    /// The number of instances of the same type allocated before this one
+    pub fn index(&self) -> usize { self.0 }
+}
+
+/// A future describing a single simulated process.
+pub type Process<'a> = Pin<Box<dyn Future<Output = ()> + 'a>>;
+
+/// A list of named processes belonging to a module. 
+///
+/// When scheduled with 
+/// [`Engine::schedule_module`](crate::engine::Engine::schedule_module), 
+/// each task is named `{instance}.{process}` (or just `{instance}` for 
+/// a process with an empty name).
+#[derive(Default)]
+pub struct Processes<'a> {
+    procs: Vec<(Cow<'static, str>, Process<'a>)>,
+}
+impl <'a> Processes<'a> {
+    pub fn new() -> Self { 
+        Self { procs: Vec::new() }
+    }
+
+    /// Add a process. 
+    pub fn with(mut self, name: impl Into<Cow<'static, str>>, 
+        fut: impl Future<Output = ()> + 'a) -> Self 
+    {
+        self.push(name, fut);
+        self
+    }
+
+    /// Add a process. 
+    pub fn push(&mut self, name: impl Into<Cow<'static, str>>, 
+        fut: impl Future<Output = ()> + 'a) 
+    {
+        self.procs.push((name.into(), Box::pin(fut)));
+    }
+
+    /// Return the number of processes.
+    pub fn len(&self) -> usize { self.procs.len() }
+
+    /// Returns 'true' if there are no processes.
+    pub fn is_empty(&self) -> bool { self.procs.is_empty() }
+}
+impl <'a> IntoIterator for Processes<'a> {
+    type Item = (Cow<'static, str>, Process<'a>);
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+    fn into_iter(self) -> Self::IntoIter { self.procs.into_iter() }
+}
+
+/// Returns the name of type `T` without its path or generic parameters 
+/// (ie. `ROM` for `rom::ROM<2>`).
+pub fn type_name<T: ?Sized>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    let base = name.split('<').next().unwrap_or(name);
+    base.rsplit("::").next().unwrap_or(base)
+}
+
 //pub struct ModuleFuture { 
 //}
 //impl Future for ModuleFuture {
@@ -0,0 +1,127 @@
+//! Finding bugs which depend on the order in which tasks are polled.
+//!
+//! The values of wires and registers in a correctly-written model do not
+//! depend on the order in which an [`Engine`](crate::engine::Engine) polls
+//! tasks. A model which only works because one task happens to be polled
+//! before another (ie. when tasks share state outside of wires and
+//! registers) can be found by simulating it several times with a shuffled
+//! task order (see
+//! [`Engine::set_task_order`](crate::engine::Engine::set_task_order)), and
+//! comparing a [`StateTrace`] from each run:
+//!
+//! ```ignore
+//! let res = compare_orders(1..=8, |seed| {
+//!     let state = EngineState::new_shareable();
+//!     let m = MyModule::new_instance(&mut state.lock().unwrap());
+//!     let trace = Rc::new(RefCell::new(StateTrace::new()));
+//!     let mut e = Engine::new(state);
+//!     e.set_task_order(seed);
+//!     e.add_checker(trace.clone());
+//!     for _ in 0..16 {
+//!         e.schedule_module(&m);
+//!         e.step();
+//!     }
+//!     trace.take()
+//! });
+//! if let Err(e) = res { panic!("{}", e); }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::check::Checker;
+use crate::engine::EngineState;
+
+/// Records the value of every wire, register, and latch at the end of each
+/// cycle.
+///
+/// Wires are named `wire{id}`, registers are named `reg{id}`, and latches
+/// are named `latch{id}` unless they have a name.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct StateTrace {
+    /// The formatted value of each signal, for each cycle. Wires which are
+    /// not driven are omitted.
+    pub cycles: Vec<BTreeMap<String, String>>,
+}
+impl StateTrace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+impl Checker for StateTrace {
+    fn check(&mut self, _cycle: usize, state: &EngineState) {
+        let mut values = BTreeMap::new();
+        for id in state.wires.data.keys().copied() {
+            if let Some(value) = state.wires.fmt_wire(id) {
+                let name = state.wires.name(id)
+                    .map_or_else(|| format!("wire{}", id), |s| s.to_string());
+                values.insert(name, value);
+            }
+        }
+        for id in state.registers.ids() {
+            let name = state.registers.name(id)
+                .map_or_else(|| format!("reg{}", id), |s| s.to_string());
+            values.insert(name, state.registers.fmt_register(id));
+        }
+        for id in state.latches.ids() {
+            let name = state.latches.name(id)
+                .map_or_else(|| format!("latch{}", id), |s| s.to_string());
+            values.insert(name, state.latches.fmt_latch(id));
+        }
+        self.cycles.push(values);
+    }
+}
+
+/// A signal whose value depends on the order in which tasks are polled.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct OrderMismatch {
+    /// The seed for the shuffled task order
+    pub seed: u64,
+    /// The first cycle in which the results differ
+    pub cycle: usize,
+    pub signal: String,
+    /// The value with tasks polled in scheduling order
+    pub expected: Option<String>,
+    /// The value with tasks polled in shuffled order
+    pub found: Option<String>,
+}
+impl fmt::Display for OrderMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fmt = |v: &Option<String>| v.clone().unwrap_or("undriven".to_string());
+        write!(f, "task order seed {}: '{}' in cycle {} is {} (expected {})",
+            self.seed, self.signal, self.cycle, fmt(&self.found), fmt(&self.expected))
+    }
+}
+
+/// Compare the results of a simulation with tasks polled in scheduling
+/// order against the results with the task order shuffled by each seed.
+///
+/// `sim` runs the simulation with the given task order (see
+/// [`Engine::set_task_order`](crate::engine::Engine::set_task_order)) and
+/// returns a trace of the results. Returns the first difference.
+pub fn compare_orders(seeds: impl IntoIterator<Item = u64>,
+    mut sim: impl FnMut(Option<u64>) -> StateTrace) -> Result<(), OrderMismatch>
+{
+    let expected = sim(None);
+    for seed in seeds {
+        let found = sim(Some(seed));
+        let cycles = expected.cycles.len().max(found.cycles.len());
+        for cycle in 0..cycles {
+            let empty = BTreeMap::new();
+            let e = expected.cycles.get(cycle).unwrap_or(&empty);
+            let f = found.cycles.get(cycle).unwrap_or(&empty);
+            let signal = e.keys().chain(f.keys())
+                .find(|k| e.get(*k) != f.get(*k));
+            if let Some(signal) = signal {
+                return Err(OrderMismatch {
+                    seed,
+                    cycle,
+                    signal: signal.clone(),
+                    expected: e.get(signal).cloned(),
+                    found: f.get(signal).cloned(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,281 @@
+//! Constrained-random stimulus.
+//!
+//! Each [`EngineState`] owns a seeded [`Rng`]. The seed is taken from the
+//! `MAFIC_SEED` environment variable when it is set, and is otherwise
+//! chosen at random. When a simulation panics after using random values,
+//! the seed is printed so that the failure can be replayed:
+//!
+//! ```text
+//! mafic: simulation failed with MAFIC_SEED=12345
+//! ```
+//!
+//! A [`Random`] describes a distribution of values, constrained by ranges,
+//! weights, and predicates. Tasks draw values from the simulation's
+//! generator with [`Random::next`], and [`drive_random`] plays random
+//! values onto a [`DriverTarget`] on every cycle:
+//!
+//! ```ignore
+//! let op = Random::range(0..=3u8).or_value(4, 7).filter(|x| *x != 2);
+//! tb.spawn("op", drive_random(&signals, &op));
+//! ```
+
+use std::future::{ Future, poll_fn };
+use std::hash::{ BuildHasher, Hasher };
+use std::ops::RangeInclusive;
+use std::sync::{ Arc, Mutex };
+use std::task::Poll;
+
+use crate::engine::EngineState;
+use crate::testbench::DriverTarget;
+
+/// The environment variable used to set the seed.
+pub const SEED_VAR: &str = "MAFIC_SEED";
+
+/// Return the seed from `MAFIC_SEED` (in decimal, or hex prefixed with
+/// `0x`), or a random seed if it is not set.
+///
+/// Panics if `MAFIC_SEED` is set but invalid.
+pub fn seed_from_env() -> u64 {
+    match std::env::var(SEED_VAR) {
+        Ok(s) => {
+            let s = s.trim();
+            let res = match s.strip_prefix("0x") {
+                Some(hex) => u64::from_str_radix(hex, 16),
+                None => s.parse(),
+            };
+            res.unwrap_or_else(|_| panic!("invalid {}: '{}'", SEED_VAR, s))
+        },
+        Err(_) => {
+            // Use the random keys from the standard library
+            std::collections::hash_map::RandomState::new()
+                .build_hasher().finish()
+        },
+    }
+}
+
+/// Prints the seeds of a simulation which panics after using random values
+/// or a shuffled task order.
+pub(crate) struct SeedGuard {
+    pub(crate) state: Arc<Mutex<EngineState>>,
+    /// The seed for the task order (if shuffled)
+    pub(crate) order_seed: Option<u64>,
+}
+impl Drop for SeedGuard {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            return;
+        }
+        // The state may have been poisoned by the panic
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.rng.used() {
+            eprintln!("mafic: simulation failed with {}={}", SEED_VAR, state.rng.seed());
+        }
+        if let Some(seed) = self.order_seed {
+            eprintln!("mafic: simulation failed with task order seed {}", seed);
+        }
+    }
+}
+
+/// A seeded pseudo-random number generator (xoshiro256**).
+#[derive(Clone, Debug)]
+pub struct Rng {
+    seed: u64,
+    s: [u64; 4],
+    /// Set once any value has been generated
+    used: bool,
+}
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Expand the seed with splitmix64
+        let mut x = seed;
+        let s = std::array::from_fn(|_| {
+            x = x.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^ (z >> 31)
+        });
+        Self { seed, s, used: false }
+    }
+
+    /// Return the seed.
+    pub fn seed(&self) -> u64 { self.seed }
+
+    /// Returns 'true' if any value has been generated.
+    pub fn used(&self) -> bool { self.used }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.used = true;
+        let s = &mut self.s;
+        let res = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        res
+    }
+
+    /// Return a value in `0..n` (or any value when `n` is zero).
+    pub fn below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            return self.next_u64();
+        }
+        // Reject values from the final partial interval
+        let zone = u64::MAX - (u64::MAX - n + 1) % n;
+        loop {
+            let x = self.next_u64();
+            if x <= zone {
+                return x % n;
+            }
+        }
+    }
+
+    /// Return 'true' with probability `p`.
+    pub fn chance(&mut self, p: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+
+    /// Return a value in a range.
+    pub fn range<T: Uniform>(&mut self, range: RangeInclusive<T>) -> T {
+        T::sample(self, *range.start(), *range.end())
+    }
+
+    /// Return one of the given values.
+    pub fn choose<'v, T>(&mut self, values: &'v [T]) -> &'v T {
+        assert!(!values.is_empty(), "cannot choose from an empty slice");
+        &values[self.below(values.len() as u64) as usize]
+    }
+}
+
+/// Trait implemented on types that can be sampled uniformly from a range.
+pub trait Uniform: Copy {
+    /// Return a value in `lo..=hi`.
+    fn sample(rng: &mut Rng, lo: Self, hi: Self) -> Self;
+}
+macro_rules! impl_uniform {
+    ($($ty:ty),*) => { $(
+        impl Uniform for $ty {
+            fn sample(rng: &mut Rng, lo: Self, hi: Self) -> Self {
+                assert!(lo <= hi, "empty range");
+                // Offsets from `lo` fit in a u64 (wrapping for full ranges)
+                let span = (hi as i128 - lo as i128) as u64;
+                let x = rng.below(span.wrapping_add(1));
+                (lo as i128 + x as i128) as $ty
+            }
+        }
+    )* };
+}
+impl_uniform!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+impl Uniform for bool {
+    fn sample(rng: &mut Rng, lo: Self, hi: Self) -> Self {
+        if lo == hi { lo } else { rng.next_u64() & 1 != 0 }
+    }
+}
+
+/// One weighted alternative in a [`Random`] distribution.
+enum Choice<T> {
+    Value(T),
+    Range(RangeInclusive<T>),
+}
+
+/// Predicate constraining the values of a [`Random`] distribution.
+type Constraint<T> = Box<dyn Fn(&T) -> bool>;
+
+/// A distribution of random values.
+///
+/// A value is drawn by picking one of the weighted alternatives, and then
+/// picking a value uniformly from it. Values which do not satisfy every
+/// predicate are drawn again.
+pub struct Random<T: Uniform> {
+    choices: Vec<(u64, Choice<T>)>,
+    constraints: Vec<Constraint<T>>,
+}
+impl <T: Uniform + 'static> Random<T> {
+    /// Maximum number of draws before giving up on the predicates
+    const MAX_TRIES: usize = 10_000;
+
+    /// Values in a range.
+    pub fn range(range: RangeInclusive<T>) -> Self {
+        Self { choices: vec![(1, Choice::Range(range))], constraints: Vec::new() }
+    }
+
+    /// A single value.
+    pub fn value(value: T) -> Self {
+        Self { choices: vec![(1, Choice::Value(value))], constraints: Vec::new() }
+    }
+
+    /// Add values in a range with the given weight (the first alternative
+    /// has weight 1).
+    pub fn or_range(mut self, weight: u64, range: RangeInclusive<T>) -> Self {
+        self.choices.push((weight, Choice::Range(range)));
+        self
+    }
+
+    /// Add a single value with the given weight (the first alternative
+    /// has weight 1).
+    pub fn or_value(mut self, weight: u64, value: T) -> Self {
+        self.choices.push((weight, Choice::Value(value)));
+        self
+    }
+
+    /// Change the weight of the first alternative.
+    pub fn with_weight(mut self, weight: u64) -> Self {
+        self.choices[0].0 = weight;
+        self
+    }
+
+    /// Only produce values which satisfy a predicate.
+    pub fn filter(mut self, f: impl Fn(&T) -> bool + 'static) -> Self {
+        self.constraints.push(Box::new(f));
+        self
+    }
+
+    /// Draw a value from a generator.
+    ///
+    /// Panics if no value satisfying the predicates is found.
+    pub fn sample(&self, rng: &mut Rng) -> T {
+        let total: u64 = self.choices.iter().map(|(w, _)| *w).sum();
+        assert!(total > 0, "distribution has no weight");
+        for _ in 0..Self::MAX_TRIES {
+            let mut pick = rng.below(total);
+            let (_, choice) = self.choices.iter()
+                .find(|(w, _)| {
+                    let found = pick < *w;
+                    pick = pick.saturating_sub(*w);
+                    found
+                })
+                .unwrap();
+            let value = match choice {
+                Choice::Value(v) => *v,
+                Choice::Range(r) => rng.range(r.clone()),
+            };
+            if self.constraints.iter().all(|f| f(&value)) {
+                return value;
+            }
+        }
+        panic!("no value satisfying the constraints after {} tries", Self::MAX_TRIES);
+    }
+
+    /// Draw a value from the simulation's generator.
+    pub fn next(&self) -> impl Future<Output = T> + '_ {
+        poll_fn(move |ctx| {
+            let state: &mut Arc<Mutex<EngineState>> = ctx.ext().downcast_mut().unwrap();
+            Poll::Ready(self.sample(&mut state.lock().unwrap().rng))
+        })
+    }
+}
+
+/// Drive a random value onto a [`DriverTarget`] forever.
+///
+/// This is a process that persists across cycles (see
+/// [`Testbench::spawn`](crate::testbench::Testbench::spawn)).
+pub async fn drive_random<D: DriverTarget>(target: &D, dist: &Random<D::Item>)
+    where D::Item: Uniform + 'static
+{
+    loop {
+        target.drive(dist.next().await).await;
+    }
+}
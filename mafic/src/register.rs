@@ -10,7 +10,9 @@ use std::task::{ Context, Poll };
 use std::pin::Pin;
 use std::any::*;
 
-use crate::engine::EngineState;
+use crate::engine::{ EngineState, next_cycle };
+use crate::logic::FourState;
+use crate::clock::{ ClockEdge, DomainId };
 
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -31,14 +33,45 @@ impl <T: std::fmt::Debug + 'static> RegisterId<T> {
 }
 impl <T: Copy + std::fmt::Debug + 'static> RegisterId<T> {
     /// Sample this wire
-    pub async fn sample<'a>(&self) -> T 
+    pub async fn sample(&self) -> T 
     {
-        SyncFuture::from_signal(self.clone()).await
+        SyncFuture::from_signal(*self).await
     }
     /// Drive this wire
-    pub async fn drive<'a>(&self, data: T)
+    pub async fn drive(&self, data: T)
     {
-        SyncDriveFuture::for_signal(self.clone(), data).await
+        SyncDriveFuture::for_signal(*self, data).await
+    }
+}
+
+impl <T: Copy + PartialEq + std::fmt::Debug + 'static> RegisterId<T> {
+    /// Wait until the value of this register differs from the value in the
+    /// previous cycle, and return the new value. 
+    ///
+    /// Completes during the first cycle (after the current cycle) which 
+    /// starts with a new value in the register. 
+    pub async fn changed(&self) -> T {
+        let mut prev = self.sample().await;
+        loop {
+            next_cycle().await;
+            let data = self.sample().await;
+            if data != prev {
+                return data;
+            }
+            prev = data;
+        }
+    }
+}
+
+impl RegisterId<bool> {
+    /// Wait until this register rises (see [`RegisterId::changed`]).
+    pub async fn posedge(&self) {
+        while !self.changed().await {}
+    }
+
+    /// Wait until this register falls (see [`RegisterId::changed`]).
+    pub async fn negedge(&self) {
+        while self.changed().await {}
     }
 }
 
@@ -59,8 +92,11 @@ where T: Copy + std::fmt::Debug + 'static
         let state: &mut Arc<Mutex<EngineState>> = ctx.ext().downcast_mut().unwrap();
 
         // Use the signal ID to get a reference to the signal's state
-        let s: Rc<RefCell<Box<dyn RegisterLike>>> = state.lock().unwrap().registers.data.get(&self.register.id)
-            .unwrap().clone();
+        let s: Rc<RefCell<Box<dyn RegisterLike>>> = {
+            let state = state.lock().unwrap();
+            state.note_register_read(self.register.id);
+            state.registers.data.get(&self.register.id).unwrap().clone()
+        };
 
         // Take ownership over the state
         //let mut s = s.lock().unwrap();
@@ -86,11 +122,12 @@ impl <T> Future for SyncDriveFuture<T>
 where T: Copy + std::fmt::Debug + 'static
 {
     type Output = ();
-    fn poll(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
         let state: &mut Arc<Mutex<EngineState>> = ctx.ext().downcast_mut().unwrap();
+        let state = state.lock().unwrap();
 
         // Use the signal ID to get a reference to the signal's state
-        let s: Rc<RefCell<Box<dyn RegisterLike>>> = state.lock().unwrap().registers.data.get(&self.register.id)
+        let s: Rc<RefCell<Box<dyn RegisterLike>>> = state.registers.data.get(&self.register.id)
             .unwrap().clone();
 
         // Take ownership over the state
@@ -100,7 +137,17 @@ where T: Copy + std::fmt::Debug + 'static
         // Downcast the signal's state into the concrete type
         let s = s.as_any_mut().downcast_mut::<RegisterState<T>>().unwrap();
 
-        s.next = Some(self.data);
+        if s.forced.is_some() {
+            state.note_ignored_drive(|| {
+                state.registers.name(self.register.id)
+                    .map_or_else(|| format!("reg{}", self.register.id), |s| s.to_string())
+            }, &self.data);
+        } else {
+            s.next = Some(self.data);
+            if state.holds_drives() {
+                state.held_registers.borrow_mut().insert(self.register.id);
+            }
+        }
 
         Poll::Ready(())
     }
@@ -117,23 +164,61 @@ pub struct RegisterState<T: Clone + std::fmt::Debug> {
     pub reset_data: T,
     /// Abstract "input wire" to this register
     pub next: Option<T>,
+    /// The value this register is forced to, and the number of remaining 
+    /// updates (or `None` until released). Drives are ignored while forced.
+    pub forced: Option<(T, Option<usize>)>,
 }
 impl <T: Clone + std::fmt::Debug + 'static> RegisterLike for RegisterState<T> {
     fn reset(&mut self) {
         self.data = self.reset_data.clone();
+        if let Some((data, _)) = &self.forced {
+            self.data = data.clone();
+        }
     }
-    fn update(&mut self) {
+    fn update(&mut self) -> bool {
+        if let Some((data, cycles)) = &mut self.forced {
+            self.next = None;
+            self.data = data.clone();
+            if let Some(n) = cycles {
+                *n -= 1;
+                if *n == 0 {
+                    self.forced = None;
+                }
+            }
+            return false;
+        }
         if let Some(data) = self.next.take() { 
             self.data = data;
+            true
+        } else { 
+            false
         }
     }
+    fn discard(&mut self) {
+        self.next = None;
+    }
+    fn release(&mut self) {
+        self.forced = None;
+    }
+    fn is_forced(&self) -> bool { self.forced.is_some() }
+    fn data_debug(&self) -> &dyn std::fmt::Debug { &self.data }
     fn as_any(&self) -> &dyn Any { self }
     fn as_any_mut(&mut self) -> &mut dyn Any { self }
 }
 
 pub trait RegisterLike { 
     fn reset(&mut self);
-    fn update(&mut self);
+    /// Commit the next value, returning 'true' if a value was committed
+    fn update(&mut self) -> bool;
+    /// Discard the next value
+    fn discard(&mut self);
+    /// Stop forcing this register (keeping the forced value until the next
+    /// update)
+    fn release(&mut self);
+    /// Returns 'true' if this register is forced
+    fn is_forced(&self) -> bool;
+    /// Return a type-erased reference to the current value
+    fn data_debug(&self) -> &dyn std::fmt::Debug;
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
 }
@@ -142,12 +227,33 @@ pub type RegisterMapInner = Rc<RefCell<Box<dyn Any + 'static>>>;
 pub struct RegisterMap {
     /// Type-erased container for [RegisterState] 
     data: BTreeMap<usize, Rc<RefCell<Box<dyn RegisterLike>>>>,
+
+    /// Human-readable names for registers
+    pub names: BTreeMap<usize, String>,
+
+    /// When set, registers allocated with [`RegisterMap::alloc_four_state`]
+    /// power up as X
+    pub power_up_x: bool,
+
+    /// Clock domain of each register outside of [`DomainId::DEFAULT`]
+    domains: BTreeMap<usize, DomainId>,
+
+    /// Clock edge of each register which does not commit on [`ClockEdge::Pos`]
+    edges: BTreeMap<usize, ClockEdge>,
+
     next_sid: usize,
 }
+impl Default for RegisterMap {
+    fn default() -> Self { Self::new() }
+}
 impl RegisterMap {
     pub fn new() -> Self { 
         Self { 
             data: BTreeMap::new(),
+            names: BTreeMap::new(),
+            power_up_x: false,
+            domains: BTreeMap::new(),
+            edges: BTreeMap::new(),
             next_sid: 1,
         }
     }
@@ -167,19 +273,117 @@ impl RegisterMap {
                 data: init,
                 reset_data: init,
                 next: None,
+                forced: None,
             })))
         );
         self.next_sid += 1;
         res
     }
 
+    /// Allocate a four-state register with the given reset value.
+    ///
+    /// In four-state simulation, this register powers up as X, and only 
+    /// takes on the reset value after [`RegisterMap::reset`]. 
+    pub fn alloc_four_state<T>(&mut self, init: T) -> RegisterId<T> 
+        where T: FourState + std::fmt::Debug + 'static
+    {
+        let res = self.alloc(init);
+        if self.power_up_x {
+            let mut s = self.data.get(&res.id).unwrap().borrow_mut();
+            let s = s.as_any_mut().downcast_mut::<RegisterState<T>>().unwrap();
+            s.data = T::x();
+        }
+        res
+    }
+
+    /// Allocate a register with a human-readable name.
+    pub fn alloc_named<T>(&mut self, name: impl Into<String>, init: T) 
+        -> RegisterId<T> 
+        where T: Copy + std::fmt::Debug + 'static
+    {
+        let res = self.alloc(init);
+        self.set_name(res, name);
+        res
+    }
+
+    /// Allocate a register in the given clock domain.
+    pub fn alloc_in<T>(&mut self, domain: DomainId, init: T) -> RegisterId<T>
+        where T: Copy + std::fmt::Debug + 'static
+    {
+        let res = self.alloc(init);
+        self.set_domain(res, domain);
+        res
+    }
+
+    /// Move a register into the given clock domain.
+    pub fn set_domain<T>(&mut self, reg: RegisterId<T>, domain: DomainId) {
+        if domain == DomainId::DEFAULT {
+            self.domains.remove(&reg.id);
+        } else {
+            self.domains.insert(reg.id, domain);
+        }
+    }
+
+    /// Return the clock domain of a register.
+    pub fn domain(&self, id: usize) -> DomainId {
+        self.domains.get(&id).copied().unwrap_or(DomainId::DEFAULT)
+    }
+
+    /// Allocate a register which commits on the given clock edge.
+    pub fn alloc_edge<T>(&mut self, edge: ClockEdge, init: T) -> RegisterId<T>
+        where T: Copy + std::fmt::Debug + 'static
+    {
+        let res = self.alloc(init);
+        self.set_edge(res, edge);
+        res
+    }
+
+    /// Change the clock edge on which a register commits.
+    pub fn set_edge<T>(&mut self, reg: RegisterId<T>, edge: ClockEdge) {
+        if edge == ClockEdge::Pos {
+            self.edges.remove(&reg.id);
+        } else {
+            self.edges.insert(reg.id, edge);
+        }
+    }
+
+    /// Return the clock edge on which a register commits.
+    pub fn edge(&self, id: usize) -> ClockEdge {
+        self.edges.get(&id).copied().unwrap_or_default()
+    }
+
+    /// Returns 'true' if any register commits on the falling edge (which 
+    /// splits every cycle in two halves, see [`crate::clock`]).
+    pub fn has_negedge(&self) -> bool {
+        !self.edges.is_empty()
+    }
+
+    /// Set the human-readable name of a register.
+    pub fn set_name<T>(&mut self, reg: RegisterId<T>, name: impl Into<String>) {
+        self.names.insert(reg.id, name.into());
+    }
+
+    /// Return the human-readable name of a register (if it has one).
+    pub fn name(&self, id: usize) -> Option<&str> {
+        self.names.get(&id).map(|s| s.as_str())
+    }
+
+    /// Return the identifiers for all tracked registers.
+    pub fn ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.data.keys().copied()
+    }
+
+    /// Return the type-erased state of a register.
+    pub(crate) fn get(&self, id: usize) -> Option<&Rc<RefCell<Box<dyn RegisterLike>>>> {
+        self.data.get(&id)
+    }
+
     pub fn peek_register<T: Copy + std::fmt::Debug + 'static>
         (&self, register: RegisterId<T>) -> T
     {
         //let s: Arc<Mutex<Box<dyn Any>>>; 
-        let s: Rc<RefCell<Box<dyn RegisterLike>>>; 
-
-        s = self.data.get(&register.id()).unwrap().clone();
+        let s: Rc<RefCell<Box<dyn RegisterLike>>> = 
+            self.data.get(&register.id()).unwrap().clone();
 
         // Take ownership over the state
         //let mut s = s.lock().unwrap();
@@ -195,12 +399,81 @@ impl RegisterMap {
         s.data
     }
 
+    /// Reset all of the registers to their reset values.
+    pub fn reset(&mut self) {
+        for item in &self.data {
+            let mut b = item.1.borrow_mut();
+            b.reset();
+        }
+    }
+
     /// Propagate updates to all tracked registers.
-    pub fn update(&mut self) {
+    ///
+    /// Returns the identifiers of all registers which were written.
+    pub fn update(&mut self) -> Vec<usize> {
+        let mut written = Vec::new();
         for item in &self.data {
             let mut b = item.1.borrow_mut();
-            b.update();
+            if b.update() { 
+                written.push(*item.0);
+            }
         }
+        written
+    }
+
+    /// Propagate updates to registers in clock domains which have an edge,
+    /// and discard updates to all other registers.
+    ///
+    /// Updates to registers which only commit on the falling edge are 
+    /// discarded. 
+    ///
+    /// Returns the identifiers of all registers which were written.
+    pub fn update_edges(&mut self, is_edge: impl Fn(DomainId) -> bool) 
+        -> Vec<usize> 
+    {
+        let mut written = Vec::new();
+        for item in &self.data {
+            let mut b = item.1.borrow_mut();
+            if !is_edge(self.domain(*item.0)) || !self.edge(*item.0).is_pos() {
+                b.discard();
+            } else if b.update() { 
+                written.push(*item.0);
+            }
+        }
+        written
+    }
+
+    /// Propagate updates to registers which commit on the falling edge in 
+    /// clock domains which have an edge. 
+    ///
+    /// Updates to other falling-edge registers are discarded. Updates to 
+    /// rising-edge registers are also discarded (they are driven again 
+    /// during the second half of the cycle), except for the registers in 
+    /// `held`, which are kept until [`RegisterMap::update_edges`].
+    ///
+    /// Returns the identifiers of all registers which were written.
+    pub fn update_negedges(&mut self, is_edge: impl Fn(DomainId) -> bool, 
+        held: &BTreeSet<usize>) -> Vec<usize> 
+    {
+        let mut written = Vec::new();
+        for (id, b) in &self.data {
+            let mut b = b.borrow_mut();
+            if !self.edges.contains_key(id) {
+                if !held.contains(id) {
+                    b.discard();
+                }
+            } else if !is_edge(self.domain(*id)) {
+                b.discard();
+            } else if b.update() { 
+                written.push(*id);
+            }
+        }
+        written
+    }
+
+    /// Format the current value of a register with [`std::fmt::Debug`].
+    pub fn fmt_register(&self, id: usize) -> String {
+        format!("{:?}", self.data.get(&id).unwrap().borrow().data_debug())
     }
 
 }
@@ -0,0 +1,301 @@
+//! Scoreboards for comparing observed transactions against a reference
+//! model.
+//!
+//! A [`Scoreboard`] receives expected transactions (ie. computed by a
+//! golden reference model) and observed transactions (ie. from a
+//! [`Monitor`](crate::testbench::Monitor)), and matches them at the end of
+//! each cycle:
+//!
+//! ```ignore
+//! let input = Monitor::new(fifo.enq);
+//! let output = Monitor::new(fifo.deq);
+//! let sb = Rc::new(RefCell::new(Scoreboard::new(MatchOrder::InOrder)
+//!     .with_timeout(8)));
+//! sb.borrow_mut().expected_from_model(input.subscribe(), |x| x);
+//! sb.borrow_mut().observed_from(output.subscribe());
+//! tb.engine().add_checker(sb.clone());
+//! tb.run_cycles(100);
+//! let report = sb.borrow_mut().finish();
+//! assert!(report.is_ok(), "{}", report);
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::mpsc;
+
+use crate::check::Checker;
+use crate::engine::EngineState;
+use crate::testbench::Observed;
+
+/// How a [`Scoreboard`] matches observed transactions with expected ones.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MatchOrder {
+    /// Each observed transaction must match the oldest expected transaction
+    InOrder,
+    /// Each observed transaction may match any expected transaction
+    OutOfOrder,
+}
+
+/// The kind of a [`Mismatch`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MismatchKind {
+    /// An observed transaction differs from the expected transaction
+    Mismatch,
+    /// An observed transaction was never expected
+    Unexpected,
+    /// An expected transaction was not observed within the timeout
+    Timeout,
+    /// An expected transaction was never observed
+    Missing,
+}
+
+/// A failure reported by a [`Scoreboard`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Mismatch {
+    /// The cycle of the observed transaction (or for transactions that
+    /// were never observed, the cycle of the expected transaction)
+    pub cycle: usize,
+    pub kind: MismatchKind,
+    /// The expected value (formatted with [`std::fmt::Debug`])
+    pub expected: Option<String>,
+    /// The observed value (formatted with [`std::fmt::Debug`])
+    pub observed: Option<String>,
+}
+
+/// The final results from a [`Scoreboard`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ScoreboardReport {
+    /// Number of observed transactions which matched
+    pub matched: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+impl ScoreboardReport {
+    /// Returns 'true' if every transaction matched.
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+impl fmt::Display for ScoreboardReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "scoreboard report ({} matched, {} mismatched)",
+            self.matched, self.mismatches.len())?;
+        for m in &self.mismatches {
+            write!(f, "  cycle {}: {:?}", m.cycle, m.kind)?;
+            if let Some(expected) = &m.expected {
+                write!(f, ", expected {}", expected)?;
+            }
+            if let Some(observed) = &m.observed {
+                write!(f, ", observed {}", observed)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Polls for the next transaction from a source.
+type Source<T> = Box<dyn FnMut() -> Option<Observed<T>>>;
+
+/// Matches observed transactions against expected transactions.
+///
+/// Transactions are added directly with [`Scoreboard::expect`] and
+/// [`Scoreboard::observe`], or received from monitors attached with
+/// [`Scoreboard::expected_from`] and [`Scoreboard::observed_from`].
+///
+/// When attached with
+/// [`Engine::add_checker`](crate::engine::Engine::add_checker), pending
+/// transactions are matched at the end of each cycle. Call
+/// [`Scoreboard::finish`] after the simulation to flag transactions that
+/// were never matched.
+pub struct Scoreboard<T: PartialEq + fmt::Debug> {
+    order: MatchOrder,
+    /// Number of cycles a transaction may wait for a match (if any)
+    timeout: Option<usize>,
+
+    expected_sources: Vec<Source<T>>,
+    observed_sources: Vec<Source<T>>,
+
+    /// Expected transactions which have not been observed yet
+    expected: VecDeque<Observed<T>>,
+    /// Observed transactions which have not been expected yet
+    observed: VecDeque<Observed<T>>,
+
+    /// The most recent cycle
+    cycle: usize,
+    matched: usize,
+    /// Failures observed so far
+    pub mismatches: Vec<Mismatch>,
+}
+impl <T: PartialEq + fmt::Debug + 'static> Scoreboard<T> {
+    pub fn new(order: MatchOrder) -> Self {
+        Self {
+            order,
+            timeout: None,
+            expected_sources: Vec::new(),
+            observed_sources: Vec::new(),
+            expected: VecDeque::new(),
+            observed: VecDeque::new(),
+            cycle: 0,
+            matched: 0,
+            mismatches: Vec::new(),
+        }
+    }
+
+    /// Flag transactions left unmatched for more than `cycles` cycles.
+    pub fn with_timeout(mut self, cycles: usize) -> Self {
+        self.timeout = Some(cycles);
+        self
+    }
+
+    /// Expect a transaction, starting from the most recent cycle.
+    pub fn expect(&mut self, item: T) {
+        let cycle = self.cycle;
+        self.expect_at(cycle, item);
+    }
+
+    /// Expect a transaction, starting from the given cycle.
+    pub fn expect_at(&mut self, cycle: usize, item: T) {
+        self.expected.push_back(Observed { cycle, item });
+    }
+
+    /// Add an observed transaction.
+    pub fn observe(&mut self, observed: Observed<T>) {
+        self.observed.push_back(observed);
+    }
+
+    /// Receive expected transactions from a channel.
+    pub fn expected_from(&mut self, rx: mpsc::Receiver<Observed<T>>) {
+        self.expected_sources.push(Box::new(move || rx.try_recv().ok()));
+    }
+
+    /// Compute expected transactions by passing each transaction received
+    /// from a channel (ie. the inputs to a design) through a reference
+    /// model.
+    pub fn expected_from_model<I: 'static>(&mut self,
+        rx: mpsc::Receiver<Observed<I>>, mut model: impl FnMut(I) -> T + 'static)
+    {
+        self.expected_sources.push(Box::new(move || {
+            rx.try_recv().ok().map(|o| Observed { cycle: o.cycle, item: model(o.item) })
+        }));
+    }
+
+    /// Receive observed transactions from a channel.
+    pub fn observed_from(&mut self, rx: mpsc::Receiver<Observed<T>>) {
+        self.observed_sources.push(Box::new(move || rx.try_recv().ok()));
+    }
+
+    /// Return the number of expected and observed transactions waiting for
+    /// a match.
+    pub fn pending(&self) -> (usize, usize) {
+        (self.expected.len(), self.observed.len())
+    }
+
+    /// Match all pending transactions, flag any left unmatched, and return
+    /// the results.
+    pub fn finish(&mut self) -> ScoreboardReport {
+        self.update();
+        for e in self.expected.drain(..) {
+            self.mismatches.push(Mismatch {
+                cycle: e.cycle,
+                kind: MismatchKind::Missing,
+                expected: Some(format!("{:?}", e.item)),
+                observed: None,
+            });
+        }
+        for o in self.observed.drain(..) {
+            self.mismatches.push(Mismatch {
+                cycle: o.cycle,
+                kind: MismatchKind::Unexpected,
+                expected: None,
+                observed: Some(format!("{:?}", o.item)),
+            });
+        }
+        ScoreboardReport {
+            matched: self.matched,
+            mismatches: self.mismatches.clone(),
+        }
+    }
+
+    /// Receive transactions from all sources and match them.
+    fn update(&mut self) {
+        for source in self.expected_sources.iter_mut() {
+            while let Some(e) = source() {
+                self.expected.push_back(e);
+            }
+        }
+        for source in self.observed_sources.iter_mut() {
+            while let Some(o) = source() {
+                self.observed.push_back(o);
+            }
+        }
+
+        match self.order {
+            MatchOrder::InOrder => {
+                while !self.expected.is_empty() && !self.observed.is_empty() {
+                    let e = self.expected.pop_front().unwrap();
+                    let o = self.observed.pop_front().unwrap();
+                    if e.item == o.item {
+                        self.matched += 1;
+                    } else {
+                        self.mismatches.push(Mismatch {
+                            cycle: o.cycle,
+                            kind: MismatchKind::Mismatch,
+                            expected: Some(format!("{:?}", e.item)),
+                            observed: Some(format!("{:?}", o.item)),
+                        });
+                    }
+                }
+            },
+            MatchOrder::OutOfOrder => {
+                let mut unmatched = VecDeque::new();
+                for o in self.observed.drain(..) {
+                    match self.expected.iter().position(|e| e.item == o.item) {
+                        Some(idx) => {
+                            self.expected.remove(idx);
+                            self.matched += 1;
+                        },
+                        None => unmatched.push_back(o),
+                    }
+                }
+                self.observed = unmatched;
+            },
+        }
+    }
+
+    /// Flag transactions which have waited longer than the timeout.
+    fn check_timeouts(&mut self) {
+        let Some(timeout) = self.timeout else { return };
+        let now = self.cycle;
+        let expired = |cycle: usize| now > cycle + timeout;
+
+        let (expired_e, pending_e) = self.expected.drain(..)
+            .partition::<VecDeque<_>, _>(|e| expired(e.cycle));
+        let (expired_o, pending_o) = self.observed.drain(..)
+            .partition::<VecDeque<_>, _>(|o| expired(o.cycle));
+        self.expected = pending_e;
+        self.observed = pending_o;
+        for e in expired_e {
+            self.mismatches.push(Mismatch {
+                cycle: e.cycle,
+                kind: MismatchKind::Timeout,
+                expected: Some(format!("{:?}", e.item)),
+                observed: None,
+            });
+        }
+        for o in expired_o {
+            self.mismatches.push(Mismatch {
+                cycle: o.cycle,
+                kind: MismatchKind::Unexpected,
+                expected: None,
+                observed: Some(format!("{:?}", o.item)),
+            });
+        }
+    }
+}
+impl <T: PartialEq + fmt::Debug + 'static> Checker for Scoreboard<T> {
+    fn check(&mut self, cycle: usize, _state: &EngineState) {
+        self.cycle = cycle;
+        self.update();
+        self.check_timeouts();
+    }
+}
@@ -0,0 +1,289 @@
+//! Drivers, monitors, and a harness for writing testbenches.
+//!
+//! - A [`Driver`] plays a queue of transactions onto a [`DriverTarget`]
+//!   (ie. a [`Decoupled`] channel, or a bundle of wires wrapped in
+//!   [`Signals`]), one transaction after another across cycles
+//! - A [`Monitor`] reconstructs transactions from a [`MonitorSource`] on
+//!   each cycle, and sends them to its subscribers
+//! - A [`Testbench`] owns an [`Engine`], schedules modules on every cycle,
+//!   and runs drivers and monitors as processes that persist across cycles
+//!
+//! ```ignore
+//! let driver = Driver::new(fifo.enq);
+//! let monitor = Monitor::new(fifo.deq);
+//! let rx = monitor.subscribe();
+//! let mut tb = Testbench::new(state);
+//! tb.add_module(&fifo);
+//! tb.add_driver("enq", &driver);
+//! tb.add_monitor("deq", &monitor);
+//! driver.extend([1, 2, 3]);
+//! tb.run_cycles(10);
+//! ```
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::{ Arc, Mutex, mpsc };
+
+use crate::bundle::Bundle;
+use crate::decoupled::Decoupled;
+use crate::engine::{ Engine, EngineErr, EngineState, current_cycle, next_cycle };
+use crate::module::ModuleLike;
+
+/// Trait implemented on signals that a [`Driver`] can play transactions
+/// onto.
+pub trait DriverTarget {
+    type Item;
+
+    /// Drive a transaction. Completes at the start of the cycle after the
+    /// transaction has completed.
+    async fn drive(&self, item: Self::Item);
+
+    /// Drive nothing during this cycle. Completes at the start of the next
+    /// cycle.
+    async fn idle(&self);
+}
+
+/// Trait implemented on signals that a [`Monitor`] can observe
+/// transactions on.
+pub trait MonitorSource {
+    type Item;
+
+    /// Return the transaction which occurred during this cycle (if any).
+    async fn observe(&self) -> Option<Self::Item>;
+}
+
+/// Transactions on a [`Decoupled`] channel are values transferred from the
+/// producer to the consumer.
+impl <T: Copy + std::fmt::Debug + 'static> DriverTarget for Decoupled<T> {
+    type Item = T;
+    async fn drive(&self, item: T) { self.producer().send(item).await }
+    async fn idle(&self) { self.producer().idle().await }
+}
+impl <T: Copy + std::fmt::Debug + 'static> MonitorSource for Decoupled<T> {
+    type Item = T;
+    async fn observe(&self) -> Option<T> {
+        if self.fire().await { Some(self.data.sample().await) } else { None }
+    }
+}
+
+/// A bundle of wires which carries one transaction on every cycle.
+///
+/// When used as a [`DriverTarget`], the `idle` value (if any) is driven on
+/// cycles without a transaction.
+pub struct Signals<B: Bundle> {
+    pub bundle: B,
+    pub idle: Option<B::Data>,
+}
+impl <B: Bundle> Signals<B> {
+    pub fn new(bundle: B) -> Self {
+        Self { bundle, idle: None }
+    }
+
+    /// Drive `data` on cycles without a transaction.
+    pub fn with_idle(mut self, data: B::Data) -> Self {
+        self.idle = Some(data);
+        self
+    }
+}
+impl <B: Bundle> DriverTarget for Signals<B> where B::Data: Clone {
+    type Item = B::Data;
+    async fn drive(&self, item: B::Data) {
+        self.bundle.drive_all(item).await;
+        next_cycle().await;
+    }
+    async fn idle(&self) {
+        if let Some(data) = &self.idle {
+            self.bundle.drive_all(data.clone()).await;
+        }
+        next_cycle().await;
+    }
+}
+impl <B: Bundle> MonitorSource for Signals<B> {
+    type Item = B::Data;
+    async fn observe(&self) -> Option<B::Data> {
+        Some(self.bundle.sample_all().await)
+    }
+}
+
+/// Plays a queue of transactions onto a [`DriverTarget`].
+///
+/// Transactions can be added while the simulation is running.
+pub struct Driver<D: DriverTarget> {
+    target: D,
+    queue: RefCell<VecDeque<D::Item>>,
+}
+impl <D: DriverTarget> Driver<D> {
+    pub fn new(target: D) -> Self {
+        Self { target, queue: RefCell::new(VecDeque::new()) }
+    }
+
+    /// Add a transaction to the queue.
+    pub fn push(&self, item: D::Item) {
+        self.queue.borrow_mut().push_back(item);
+    }
+
+    /// Add several transactions to the queue.
+    pub fn extend(&self, items: impl IntoIterator<Item = D::Item>) {
+        self.queue.borrow_mut().extend(items);
+    }
+
+    /// Return the number of transactions waiting to be driven.
+    pub fn pending(&self) -> usize {
+        self.queue.borrow().len()
+    }
+
+    /// Drive transactions from the queue forever.
+    ///
+    /// This is a process that persists across cycles (see
+    /// [`Testbench::add_driver`]).
+    pub async fn run(&self) {
+        loop {
+            let item = self.queue.borrow_mut().pop_front();
+            match item {
+                Some(item) => self.target.drive(item).await,
+                None => self.target.idle().await,
+            }
+        }
+    }
+}
+
+/// A transaction observed by a [`Monitor`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Observed<T> {
+    /// The cycle in which the transaction occurred
+    pub cycle: usize,
+    pub item: T,
+}
+
+/// Reconstructs transactions from a [`MonitorSource`], and sends them to
+/// all subscribers.
+pub struct Monitor<S: MonitorSource> {
+    source: S,
+    subscribers: RefCell<Vec<mpsc::Sender<Observed<S::Item>>>>,
+}
+impl <S: MonitorSource> Monitor<S> where S::Item: Clone {
+    pub fn new(source: S) -> Self {
+        Self { source, subscribers: RefCell::new(Vec::new()) }
+    }
+
+    /// Return a channel receiving all transactions observed from now on.
+    pub fn subscribe(&self) -> mpsc::Receiver<Observed<S::Item>> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.borrow_mut().push(tx);
+        rx
+    }
+
+    /// Observe transactions forever.
+    ///
+    /// This is a process that persists across cycles (see
+    /// [`Testbench::add_monitor`]).
+    pub async fn run(&self) {
+        loop {
+            if let Some(item) = self.source.observe().await {
+                let cycle = current_cycle().await;
+                // Drop subscribers whose receivers are gone
+                self.subscribers.borrow_mut().retain(|tx| {
+                    tx.send(Observed { cycle, item: item.clone() }).is_ok()
+                });
+            }
+            next_cycle().await;
+        }
+    }
+}
+
+/// A harness which owns the [`Engine`] loop.
+///
+/// Modules added to a testbench are scheduled on every cycle. Drivers,
+/// monitors, and other processes are scheduled once, and persist across
+/// cycles.
+pub struct Testbench<'a> {
+    engine: Engine<'a>,
+    state: Arc<Mutex<EngineState>>,
+}
+impl <'a> Testbench<'a> {
+    pub fn new(state: Arc<Mutex<EngineState>>) -> Self {
+        Self { engine: Engine::new(state.clone()), state }
+    }
+
+    /// Return the simulated state.
+    pub fn state(&self) -> &Arc<Mutex<EngineState>> {
+        &self.state
+    }
+
+    /// Return the underlying [`Engine`] (ie. to add observers or checkers).
+    pub fn engine(&mut self) -> &mut Engine<'a> {
+        &mut self.engine
+    }
+
+    /// Schedule a module on every cycle (see [`Engine::add_module`]).
+    pub fn add_module<M: ModuleLike>(&mut self, module: &'a M) {
+        self.engine.add_module(module);
+    }
+
+    /// Run a process which persists across cycles.
+    pub fn spawn(&mut self, name: impl Into<Cow<'static, str>>,
+        fut: impl Future<Output = ()> + 'a)
+    {
+        self.engine.schedule(name, fut);
+    }
+
+    /// Run a [`Driver`].
+    pub fn add_driver<D: DriverTarget>(&mut self,
+        name: impl Into<Cow<'static, str>>, driver: &'a Driver<D>)
+    {
+        self.spawn(name, driver.run());
+    }
+
+    /// Run a [`Monitor`].
+    pub fn add_monitor<S: MonitorSource>(&mut self,
+        name: impl Into<Cow<'static, str>>, monitor: &'a Monitor<S>)
+        where S::Item: Clone
+    {
+        self.spawn(name, monitor.run());
+    }
+
+    /// Simulate a single cycle.
+    ///
+    /// Panics if the simulation stalls (see [`Testbench::try_step`]).
+    pub fn step(&mut self) {
+        if let Err(e) = self.try_step() {
+            panic!("{}", e);
+        }
+    }
+
+    /// Simulate a single cycle.
+    pub fn try_step(&mut self) -> Result<(), EngineErr> {
+        self.engine.try_step()
+    }
+
+    /// Simulate `n` cycles.
+    ///
+    /// Panics if the simulation stalls (see [`Testbench::try_run_cycles`]).
+    pub fn run_cycles(&mut self, n: usize) -> usize {
+        self.try_run_cycles(n).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Simulate `n` cycles (see [`Engine::run_cycles`]).
+    pub fn try_run_cycles(&mut self, n: usize) -> Result<usize, EngineErr> {
+        self.engine.run_cycles(n)
+    }
+
+    /// Simulate cycles until `pred` holds, returning the number of cycles
+    /// simulated.
+    ///
+    /// Panics if the simulation stalls or times out (see
+    /// [`Testbench::try_run_until`]).
+    pub fn run_until(&mut self, pred: impl Fn(&EngineState) -> bool) -> usize {
+        self.try_run_until(pred).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Simulate cycles until `pred` holds, returning the number of cycles
+    /// simulated (see [`Engine::run_until`]).
+    pub fn try_run_until(&mut self, pred: impl Fn(&EngineState) -> bool) 
+        -> Result<usize, EngineErr>
+    {
+        self.engine.run_until(pred)
+    }
+}
@@ -10,7 +10,8 @@ use std::task::{ Context, Poll };
 use std::pin::Pin;
 use std::any::*;
 
-use crate::engine::EngineState;
+use crate::engine::{ EngineState, next_cycle };
+use crate::logic::FourState;
 
 /// The direction of a wire
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -49,22 +50,64 @@ impl <T: std::fmt::Debug + 'static> WireId<T> {
 
 impl <T: Copy + std::fmt::Debug + 'static> WireId<T> {
     /// Sample the value on this wire
-    pub async fn sample<'a>(&self) -> T 
+    pub async fn sample(&self) -> T 
     {
-        CombFuture::from_wire(self.clone()).await
+        CombFuture::from_wire(*self).await
+    }
+    /// Sample the value on this wire, or return `None` if it is not driven 
+    /// during the current cycle. 
+    ///
+    /// Unlike [`WireId::sample`], an undriven wire does not stall the 
+    /// engine: the result is `None` at the start of the next cycle. 
+    pub async fn try_sample(&self) -> Option<T>
+    {
+        TrySampleFuture::from_wire(*self).await
     }
     /// Drive this wire with the given value
-    pub async fn drive<'a>(&self, data: T)
+    pub async fn drive(&self, data: T)
     {
-        CombDriveFuture::for_wire(self.clone(), data).await
+        CombDriveFuture::for_wire(*self, data).await
     }
 
     /// Drive this wire with the value on another wire
-    pub async fn assign<'a>(&self, other: Self)
+    pub async fn assign(&self, other: Self)
     {
-        AssignFuture::for_wires(other, self.clone()).await
+        AssignFuture::for_wires(other, *self).await
+    }
+
+}
+
+impl <T: Copy + PartialEq + std::fmt::Debug + 'static> WireId<T> {
+    /// Wait until the value on this wire differs from the value in the 
+    /// last cycle in which it was driven, and return the new value. 
+    ///
+    /// The wire is sampled on every cycle (starting with the current cycle)
+    /// with [`WireId::try_sample`], so cycles where it is not driven are 
+    /// ignored. Completes during the cycle in which the change is observed. 
+    pub async fn changed(&self) -> T {
+        let mut prev = None;
+        loop {
+            // An undriven wire completes at the start of the next cycle
+            let Some(data) = self.try_sample().await else { continue };
+            if prev.is_some_and(|p| p != data) {
+                return data;
+            }
+            prev = Some(data);
+            next_cycle().await;
+        }
+    }
+}
+
+impl WireId<bool> {
+    /// Wait until this wire rises (see [`WireId::changed`]).
+    pub async fn posedge(&self) {
+        while !self.changed().await {}
     }
 
+    /// Wait until this wire falls (see [`WireId::changed`]).
+    pub async fn negedge(&self) {
+        while self.changed().await {}
+    }
 }
 
 
@@ -97,11 +140,52 @@ where T: Copy + std::fmt::Debug + 'static
         if let Some(result) = wire_data {
             Poll::Ready(result)
         } else { 
+            state.lock().unwrap().wait_on_wire(self.wire.id());
             Poll::Pending
         }
     }
 }
 
+/// Future representing a read from a simulated wire which may not be driven
+/// (see [`WireId::try_sample`]). 
+pub struct TrySampleFuture<T> { 
+    /// The target [`WireId`]
+    wire: WireId<T>,
+    /// The cycle of the first poll
+    cycle: Option<usize>,
+}
+impl <T> TrySampleFuture<T> {
+    pub fn from_wire(wire: WireId<T>) -> Self { 
+        Self { wire, cycle: None }
+    }
+}
+impl <T> Unpin for TrySampleFuture<T> {}
+impl <T> Future for TrySampleFuture<T> 
+where T: Copy + std::fmt::Debug + 'static
+{
+    type Output = Option<T>;
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let state: &mut Arc<Mutex<EngineState>> = {
+            ctx.ext().downcast_mut().unwrap()
+        };
+        let state = state.lock().unwrap();
+        let cycle = *self.cycle.get_or_insert(state.cycle);
+
+        // The wire was not driven before the end of the cycle
+        if state.cycle > cycle {
+            return Poll::Ready(None);
+        }
+        if let Some(result) = state.read_wire(self.wire) {
+            return Poll::Ready(Some(result));
+        }
+
+        // Wait for the wire to be driven, or for the next cycle
+        state.wait_on_wire(self.wire.id());
+        state.waits.borrow_mut().sleep_until(cycle + 1);
+        Poll::Pending
+    }
+}
+
 pub struct CombDriveFuture<T> { 
     wire: WireId<T>,
     data: T,
@@ -115,7 +199,7 @@ impl <T> Future for CombDriveFuture<T>
 where T: Copy + std::fmt::Debug + 'static
 {
     type Output = ();
-    fn poll(mut self: Pin<&mut Self>, ctx: &mut Context<'_>)
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>)
         -> Poll<Self::Output> 
     {
         let state: &mut Arc<Mutex<EngineState>> = ctx.ext().downcast_mut().unwrap();
@@ -137,7 +221,7 @@ impl <T> Future for AssignFuture<T>
 where T: Copy + std::fmt::Debug + 'static
 {
     type Output = ();
-    fn poll(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) 
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) 
         -> Poll<Self::Output> 
     {
         let state: &mut Arc<Mutex<EngineState>> = 
@@ -148,6 +232,7 @@ where T: Copy + std::fmt::Debug + 'static
         // task until the source wire actually obtains a value ...
         let src_value = state.lock().unwrap().read_wire(self.src);
         if src_value.is_none() {
+            state.lock().unwrap().wait_on_wire(self.src.id());
             return Poll::Pending;
         }
 
@@ -173,9 +258,50 @@ pub struct WireState<T: std::fmt::Debug> {
     ///   during the current clock cyce [and is available to be read]
     ///
     pub data: Option<T>,
+
+    /// Set when some simulated process has sampled this wire during the 
+    /// current clock cycle
+    pub sampled: bool,
+
+    /// The value read from this wire when no process drives it (ie. Z for
+    /// four-state wires). When `None`, readers block until the wire is 
+    /// driven. 
+    pub undriven: Option<T>,
+
+    /// Set when `data` holds the undriven value (see 
+    /// [`EngineState::resolve_undriven`]) instead of a driven value
+    pub resolved: bool,
+
+    /// The value this wire is forced to (see 
+    /// [`EngineState::force_wire`]). Drives are ignored while forced.
+    pub forced: Option<T>,
 }
-impl <T: std::fmt::Debug + 'static> WireLike for WireState<T> {
-    fn reset(&mut self) { self.data = None; }
+impl <T: Copy + std::fmt::Debug + 'static> WireLike for WireState<T> {
+    fn reset(&mut self) { 
+        self.data = self.forced; 
+        self.sampled = false;
+        self.resolved = false;
+    }
+    fn release(&mut self) {
+        if self.forced.take().is_some() {
+            self.data = None;
+        }
+    }
+    fn is_forced(&self) -> bool { self.forced.is_some() }
+    fn is_driven(&self) -> bool { self.data.is_some() }
+    fn is_sampled(&self) -> bool { self.sampled }
+    fn data_debug(&self) -> Option<&dyn std::fmt::Debug> { 
+        self.data.as_ref().map(|d| d as &dyn std::fmt::Debug)
+    }
+    fn resolve_undriven(&mut self) -> bool { 
+        if self.data.is_none() && self.undriven.is_some() {
+            self.data = self.undriven;
+            self.resolved = true;
+            true
+        } else { 
+            false
+        }
+    }
     fn as_any(&self) -> &dyn Any { self }
     fn as_any_mut(&mut self) -> &mut dyn Any { self }
 }
@@ -185,6 +311,26 @@ pub trait WireLike {
     /// Reset the value of this wire
     fn reset(&mut self);
 
+    /// Stop forcing this wire (leaving it undriven until the next drive)
+    fn release(&mut self);
+
+    /// Returns 'true' if this wire is forced
+    fn is_forced(&self) -> bool;
+
+    /// Returns 'true' if this wire has been driven during this cycle
+    fn is_driven(&self) -> bool;
+
+    /// Returns 'true' if this wire has been sampled during this cycle
+    fn is_sampled(&self) -> bool;
+
+    /// Return a type-erased reference to the value driven during this 
+    /// cycle (if any)
+    fn data_debug(&self) -> Option<&dyn std::fmt::Debug>;
+
+    /// If this wire has not been driven, take on the "undriven" value 
+    /// (if any). Returns 'true' if the value of this wire has changed. 
+    fn resolve_undriven(&mut self) -> bool;
+
     /// Return a type-erased reference to this object 
     fn as_any(&self) -> &dyn Any;
 
@@ -197,6 +343,13 @@ pub trait WireAllocator {
         where T: Copy + std::fmt::Debug + 'static;
 
 }
+impl WireAllocator for WireMap {
+    fn alloc_wire<T>(&mut self, name: &'static str) -> WireId<T>
+        where T: Copy + std::fmt::Debug + 'static
+    {
+        self.alloc_named(name)
+    }
+}
 
 
 pub type WireMapInner = Rc<RefCell<Box<dyn Any + 'static>>>;
@@ -204,15 +357,32 @@ pub struct WireMap {
     /// Type-erased container for [WireState] 
     pub data: BTreeMap<usize, Rc<RefCell<Box<dyn WireLike>>>>,
 
+    /// Wires belonging to each net with more than one wire, keyed by the 
+    /// root of the net (see [`WireMap::connect`])
     pub connections: BTreeMap<usize, BTreeSet<usize>>,
 
+    /// Parent of each connected wire which is not the root of its net
+    parent: BTreeMap<usize, usize>,
+
+    /// Human-readable names for wires
+    pub names: BTreeMap<usize, String>,
+
+    /// Wires which are not expected to be driven/sampled on every cycle
+    pub optional: BTreeSet<usize>,
+
     pub next_sid: usize,
 }
+impl Default for WireMap {
+    fn default() -> Self { Self::new() }
+}
 impl WireMap {
     pub fn new() -> Self { 
         Self { 
             data: BTreeMap::new(),
             connections: BTreeMap::new(),
+            parent: BTreeMap::new(),
+            names: BTreeMap::new(),
+            optional: BTreeSet::new(),
             next_sid: 1,
         }
     }
@@ -229,12 +399,57 @@ impl WireMap {
         self.data.insert(id, 
             Rc::new(RefCell::new(Box::new(WireState::<T> { 
                 data: None,
+                sampled: false,
+                undriven: None,
+                resolved: false,
+                forced: None,
             })))
         );
         self.next_sid += 1;
         res
     }
 
+    /// Allocate a four-state wire.
+    ///
+    /// In four-state simulation, this wire reads as Z when no process 
+    /// drives it (instead of blocking readers forever).
+    pub fn alloc_four_state<T>(&mut self) -> WireId<T> 
+        where T: FourState + std::fmt::Debug + 'static
+    {
+        let res = self.alloc::<T>();
+        self.data.get(&res.id).unwrap().borrow_mut().as_any_mut()
+            .downcast_mut::<WireState<T>>().unwrap()
+            .undriven = Some(T::z());
+        res
+    }
+
+    /// Allocate a wire with a human-readable name.
+    pub fn alloc_named<T: Copy + std::fmt::Debug + 'static>
+        (&mut self, name: impl Into<String>) -> WireId<T> 
+    {
+        let res = self.alloc();
+        self.set_name(res, name);
+        res
+    }
+
+    /// Set the human-readable name of a wire.
+    pub fn set_name<T>(&mut self, wire: WireId<T>, name: impl Into<String>) {
+        self.names.insert(wire.id, name.into());
+    }
+
+    /// Return the human-readable name of a wire (if it has one).
+    pub fn name(&self, id: usize) -> Option<&str> {
+        self.names.get(&id).map(|s| s.as_str())
+    }
+
+    /// Mark a wire as "expected-optional". 
+    ///
+    /// Optional wires are allowed to remain undriven (or unsampled) during
+    /// a cycle, and are ignored by [`Lint`](crate::lint::Lint).
+    pub fn set_optional<T>(&mut self, wire: WireId<T>) {
+        self.optional.insert(wire.id);
+    }
+
     pub fn peek_wire<T: Copy + std::fmt::Debug + 'static>
         (&self, wire: WireId<T>) -> Option<T>
     {
@@ -252,6 +467,75 @@ impl WireMap {
         s.data
     }
 
+    /// Format the value driven on a wire during this cycle (if any) with
+    /// [`std::fmt::Debug`].
+    pub fn fmt_wire(&self, id: usize) -> Option<String> {
+        self.data.get(&id).unwrap().borrow().data_debug()
+            .map(|d| format!("{:?}", d))
+    }
+
+    /// Connect two wires into a single net. 
+    ///
+    /// This happens during elaboration: afterwards, both wires share the 
+    /// same state, so driving either end of the net makes the value visible
+    /// on the other without any simulated task (unlike [`WireId::assign`]). 
+    /// Driving both ends of the net is a driver-to-driver error. 
+    pub fn connect<T: Copy + std::fmt::Debug + 'static>
+        (&mut self, a: WireId<T>, b: WireId<T>)
+    {
+        let (ra, rb) = (self.root(a.id), self.root(b.id));
+        if ra == rb {
+            return;
+        }
+        // Four-state wires keep reading as Z when connected to other wires
+        let undriven = self.undriven(a).or(self.undriven(b));
+
+        // Attach the smaller net to the root of the larger net
+        let size = |r| self.connections.get(&r).map_or(1, |n| n.len());
+        let (root, child) = if size(ra) >= size(rb) { (ra, rb) } else { (rb, ra) };
+        self.parent.insert(child, root);
+
+        let members = self.connections.remove(&child)
+            .unwrap_or_else(|| BTreeSet::from([child]));
+        let state = self.data.get(&root).unwrap().clone();
+        for id in members.iter() {
+            self.data.insert(*id, state.clone());
+        }
+        self.connections.entry(root)
+            .or_insert_with(|| BTreeSet::from([root]))
+            .extend(members);
+        if let Some(z) = undriven {
+            state.borrow_mut().as_any_mut()
+                .downcast_mut::<WireState<T>>().unwrap()
+                .undriven = Some(z);
+        }
+    }
+
+    /// Return the "undriven" value of a wire (if any).
+    fn undriven<T: Copy + std::fmt::Debug + 'static>(&self, wire: WireId<T>) 
+        -> Option<T>
+    {
+        self.data.get(&wire.id).unwrap().borrow_mut().as_any_mut()
+            .downcast_mut::<WireState<T>>().unwrap()
+            .undriven
+    }
+
+    /// Return the id of the wire at the root of the net containing `id`.
+    pub fn root(&self, mut id: usize) -> usize {
+        while let Some(p) = self.parent.get(&id) {
+            id = *p;
+        }
+        id
+    }
+
+    /// Return the ids of all wires in the net containing `id`.
+    pub fn net(&self, id: usize) -> Vec<usize> {
+        match self.connections.get(&self.root(id)) {
+            Some(n) => n.iter().copied().collect(),
+            None => vec![id],
+        }
+    }
+
     /// Reset all of the wires.
     pub fn reset(&mut self) {
         for item in &self.data {
@@ -260,6 +544,16 @@ impl WireMap {
         }
     }
 
+    /// Reset all of the wires, except for the nets whose roots are in 
+    /// `held`.
+    pub fn reset_except(&mut self, held: &BTreeSet<usize>) {
+        for item in &self.data {
+            if !held.contains(&self.root(*item.0)) {
+                item.1.borrow_mut().reset();
+            }
+        }
+    }
+
 
 
 }
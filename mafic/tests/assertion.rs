@@ -0,0 +1,121 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use mafic::*;
+
+/// Acknowledges each request after a fixed number of cycles.
+async fn responder(req: WireId<bool>, ack: WireId<bool>, latency: usize) {
+    let mut countdown: Option<usize> = None;
+    loop {
+        let fire = countdown == Some(0);
+        ack.drive(fire).await;
+        countdown = match countdown {
+            Some(0) | None if req.sample().await && !fire => Some(latency - 1),
+            Some(0) => None,
+            Some(n) => Some(n - 1),
+            None => None,
+        };
+        next_cycle().await;
+    }
+}
+
+fn run_handshake(latency: usize) -> Vec<AssertionFailure> {
+    let state = EngineState::new_shareable();
+    let (req, ack): (WireId<bool>, WireId<bool>) = {
+        let mut s = state.lock().unwrap();
+        (s.wires.alloc(), s.wires.alloc())
+    };
+    let driver = Driver::new(Signals::new(req).with_idle(false));
+    driver.extend([true, false, false, false, false, false, true]);
+
+    let mut a = Assertions::new();
+    let req_s = a.wire("req", req);
+    let ack_s = a.wire("ack", ack);
+    a.assert("req_ack", req_s.high().implies(ack_s.high().within(1, 4)));
+    let a = Rc::new(RefCell::new(a));
+
+    let mut tb = Testbench::new(state.clone());
+    tb.add_driver("req", &driver);
+    tb.spawn("responder", responder(req, ack, latency));
+    tb.engine().add_checker(a.clone());
+    tb.run_cycles(16);
+    a.take().failures
+}
+
+#[test]
+fn bounded_response() {
+    assert!(run_handshake(3).is_empty());
+    let failures = run_handshake(6);
+    assert_eq!(failures.len(), 2);
+    assert_eq!((failures[0].start, failures[0].cycle), (0, 4));
+    assert_eq!(failures[0].trace, [
+        ("req".to_string(), vec!["true", "false", "false", "false", "false"]
+            .into_iter().map(String::from).collect::<Vec<_>>()),
+        ("ack".to_string(), vec!["false"; 5]
+            .into_iter().map(String::from).collect::<Vec<_>>()),
+    ]);
+    let report = failures[0].to_string();
+    assert!(report.starts_with("assertion 'req_ack' failed at cycle 4 (started at cycle 0)"));
+    assert!(report.contains("  ack: false false false false false"));
+}
+
+#[test]
+fn fifo_invariants() {
+    let state = EngineState::new_shareable();
+    let fifo: Fifo<u32, 2> = Fifo::new_instance(&mut state.lock().unwrap());
+    let enq = Driver::new(fifo.enq);
+    let ready = Driver::new(Signals::new(fifo.deq.ready).with_idle(true));
+
+    let mut a = Assertions::new();
+    let full = a.wire("full", fifo.full);
+    let empty = a.wire("empty", fifo.empty);
+    let count = a.wire("count", fifo.count);
+    let enq_fire = a.wire("enq.valid", fifo.enq.valid);
+    a.assert("full_empty", !(full.high() & empty.high()));
+    // A value accepted into an empty queue is visible on the next cycle
+    a.assert("not_empty", (empty.high() & enq_fire.high()).implies(empty.low().delay(1)));
+    let (now, prev) = (count.clone(), count.past(1));
+    a.assert("count_step", Cond::from_fn(move || {
+        match (now.value(), prev.value()) {
+            (Some(n), Some(p)) => n.abs_diff(p) <= 1,
+            _ => true,
+        }
+    }));
+    // Deliberately wrong: the queue is not always empty
+    a.assert("always_empty", empty.high());
+    let a = Rc::new(RefCell::new(a));
+
+    let mut tb = Testbench::new(state.clone());
+    tb.add_module(&fifo);
+    tb.add_driver("enq", &enq);
+    tb.add_driver("ready", &ready);
+    tb.engine().add_checker(a.clone());
+    enq.extend([1, 2, 3, 4]);
+    ready.extend([false, false, false]);
+    tb.run_cycles(10);
+
+    let a = a.borrow();
+    assert!(a.failures.iter().all(|f| f.name == "always_empty"), "{:?}", a.failures);
+    assert_eq!(a.failures[0].cycle, 1);
+    assert_eq!(a.pending(), 0);
+}
+
+#[test]
+fn rose_and_fell() {
+    let state = EngineState::new_shareable();
+    let x: WireId<bool> = state.lock().unwrap().wires.alloc();
+    let driver = Driver::new(Signals::new(x).with_idle(false));
+    driver.extend([false, true, true, false]);
+
+    let mut a = Assertions::new();
+    let xs = a.wire("x", x);
+    // Every rising edge is followed by a falling edge within 2 cycles
+    a.assert("pulse", xs.rose().implies(xs.fell().within(1, 2)));
+    let a = Rc::new(RefCell::new(a));
+
+    let mut tb = Testbench::new(state.clone());
+    tb.add_driver("x", &driver);
+    tb.engine().add_checker(a.clone());
+    tb.run_cycles(6);
+    assert!(a.borrow().failures.is_empty());
+}
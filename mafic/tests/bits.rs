@@ -0,0 +1,90 @@
+use mafic::*;
+
+pub struct Adder { 
+    x: WireId<Bits<8>>,
+    y: WireId<Bits<8>>,
+    z: WireId<Bits<8>>,
+}
+impl ModuleLike for Adder { 
+    fn new_instance(state: &mut EngineState) -> Self { 
+        Self { 
+            x: state.wires.alloc(),
+            y: state.wires.alloc(),
+            z: state.wires.alloc(),
+        }
+    }
+    async fn run(&self) {
+        let x = self.x.sample().await;
+        let y = self.y.sample().await;
+        self.z.drive(x + y).await;
+    }
+}
+
+#[test]
+fn bits_adder_wraps() {
+    let state = EngineState::new_shareable();
+    let adder = Adder::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+
+    e.schedule_module(&adder);
+    e.schedule("poke", async { 
+        adder.x.drive(Bits::new(0xf0)).await;
+        adder.y.drive(Bits::new(0x20)).await;
+    });
+    e.run();
+    let z = state.lock().unwrap().wires.peek_wire(adder.z).unwrap();
+    assert_eq!(z, Bits::new(0x10));
+    assert_eq!(format!("{:?}", z), "8'h10");
+}
+
+#[test]
+fn bits_ops() {
+    let x: Bits<8> = Bits::new(0xa5);
+    assert_eq!(Bits::<4>::new(0x1f).value(), 0xf);
+    assert_eq!(Bits::<4>::try_new(0x1f), None);
+    assert_eq!(!x, Bits::new(0x5a));
+    assert_eq!(x << 4, Bits::new(0x50));
+    assert_eq!(x >> 4, Bits::new(0x0a));
+    assert_eq!(Bits::<8>::ZERO - Bits::new(1), Bits::ONES);
+
+    assert_eq!(x.slice::<4>(4), Bits::<4>::new(0xa));
+    assert_eq!(x.concat::<4, 12>(Bits::new(0x3)), Bits::<12>::new(0xa53));
+    assert_eq!(x.zext::<16>(), Bits::<16>::new(0x00a5));
+    assert_eq!(x.sext::<16>(), Bits::<16>::new(0xffa5));
+    assert_eq!(x.trunc::<4>(), Bits::<4>::new(0x5));
+    assert_eq!(x / Bits::new(0), Bits::<8>::ONES);
+    assert_eq!(x % Bits::new(0), x);
+    assert!(x.bit(0) && !x.bit(1));
+    assert_eq!(x.with_bit(1, true), Bits::new(0xa7));
+
+    assert!(Bits::<3>::new(0b111).and_reduce());
+    assert!(Bits::<3>::new(0b010).or_reduce());
+    assert!(Bits::<3>::new(0b111).xor_reduce());
+    assert!(Bits::<8>::new(0x80) > Bits::new(0x7f));
+
+    assert_eq!(u8::try_from(x), Ok(0xa5));
+    assert!(u8::try_from(Bits::<16>::new(0x100)).is_err());
+    assert_eq!(Bits::<8>::try_from(0x1ffu32), Err(bits::WidthError));
+    assert_eq!(u128::from(x), 0xa5);
+}
+
+#[test]
+fn sint_ops() {
+    let x: SInt<8> = SInt::new(-3);
+    assert_eq!(x.value(), -3);
+    assert_eq!(x.as_bits(), Bits::new(0xfd));
+    assert_eq!(format!("{:?}", x), "8'sd-3");
+    assert_eq!(SInt::<8>::MAX + SInt::new(1), SInt::<8>::MIN);
+    assert_eq!(SInt::<8>::MIN.value(), -128);
+    assert_eq!(-x, SInt::new(3));
+    assert_eq!(x >> 1, SInt::new(-2));
+    assert_eq!(x * SInt::new(3), SInt::new(-9));
+    assert_eq!(x / SInt::new(2), SInt::new(-1));
+    assert_eq!(x / SInt::new(0), SInt::new(-1));
+    assert_eq!(x % SInt::new(0), x);
+    assert_eq!(x.sext::<16>().value(), -3);
+    assert!(x < SInt::new(1));
+    assert!(x.is_negative());
+    assert_eq!(SInt::<4>::try_new(8), None);
+    assert_eq!(i8::try_from(x), Ok(-3));
+}
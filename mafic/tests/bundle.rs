@@ -0,0 +1,154 @@
+use mafic::*;
+
+/// A read request 
+#[derive(Bundle)]
+pub struct ReadPortReq { 
+    idx: WireId<usize>,
+    en: WireId<bool>,
+}
+
+/// A read response
+#[derive(Bundle)]
+pub struct ReadPortResp {
+    data: WireId<usize>,
+}
+
+/// A read port
+#[derive(Bundle)]
+pub struct ReadPort {
+    req: ReadPortReq,
+    #[bundle(flip)]
+    resp: ReadPortResp,
+}
+impl ReadPort {
+    pub fn new(e: &mut EngineState) -> Self { 
+        Self { 
+            req: ReadPortReq { idx: e.wires.alloc(), en: e.wires.alloc() },
+            resp: ReadPortResp { data: e.wires.alloc() },
+        }
+    }
+}
+
+/// A generic bundle
+#[derive(Bundle)]
+pub struct Pair<T: Copy + std::fmt::Debug + 'static, const N: usize> {
+    a: [WireId<T>; N],
+    b: WireId<T>,
+}
+
+/// A read-only memory device
+pub struct ROM<const NUM_RP: usize> {
+    rp: [ReadPort; NUM_RP],
+}
+impl <const NUM_RP: usize> ModuleLike for ROM<NUM_RP> {
+    fn new_instance(state: &mut EngineState) -> Self { 
+        Self { rp: std::array::from_fn(|_| ReadPort::new(state)) }
+    }
+    async fn run(&self) {
+        for pid in 0..NUM_RP {
+            let (idx, en) = self.rp[pid].req.sample_all().await;
+            if en {
+                self.rp[pid].resp.drive_all((idx * 2,)).await;
+            }
+        }
+    }
+}
+
+/// Exposes the ports of a ROM
+pub struct Top { 
+    rp: [ReadPort; 2],
+    rom: ROM<2>,
+}
+impl ModuleLike for Top { 
+    fn new_instance(state: &mut EngineState) -> Self { 
+        Self { 
+            rp: std::array::from_fn(|_| ReadPort::new(state)),
+            rom: ROM::new_instance(state),
+        }
+    }
+    async fn run(&self) {
+        self.rom.rp.connect(&self.rp).await;
+    }
+}
+
+#[test]
+fn connect_ports() {
+    let state = EngineState::new_shareable();
+    let top = Top::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+
+    e.schedule_module(&top);
+    e.schedule_module(&top.rom);
+    e.schedule("tb", async { 
+        top.rp[0].req.drive_all((5, true)).await;
+        top.rp[1].req.drive_all((3, false)).await;
+        // Only the first port responds
+        top.rom.rp[1].resp.data.drive(0).await;
+        let data = top.rp.sample_all().await;
+        assert_eq!(data, [((5, true), (10,)), ((3, false), (0,))]);
+    });
+    e.run();
+    assert_eq!(state.lock().unwrap().wires.peek_wire(top.rp[0].resp.data), 
+        Some(10));
+}
+
+#[test]
+fn generic_bundle() {
+    let state = EngineState::new_shareable();
+    let (x, y): (Pair<u8, 2>, Pair<u8, 2>) = { 
+        let mut s = state.lock().unwrap();
+        let mut alloc = || Pair { 
+            a: [s.wires.alloc(), s.wires.alloc()], b: s.wires.alloc()
+        };
+        (alloc(), alloc())
+    };
+    let mut e = Engine::new(state.clone());
+    e.schedule("connect", y.connect(&x));
+    e.schedule("tb", async { 
+        x.drive_all(([1, 2], 3)).await;
+        assert_eq!(y.sample_all().await, ([1, 2], 3));
+    });
+    e.run();
+}
+
+#[test]
+fn alias_ports() {
+    let state = EngineState::new_shareable();
+    let (rp, rom) = { 
+        let mut s = state.lock().unwrap();
+        let rp = ReadPort::new(&mut s);
+        let rom = ROM::<1>::new_instance(&mut s);
+        rom.rp[0].alias(&rp, &mut s.wires);
+        (rp, rom)
+    };
+    let mut e = Engine::new(state.clone());
+
+    e.schedule_module(&rom);
+    e.schedule("tb", async { 
+        rp.req.drive_all((4, true)).await;
+        assert_eq!(rp.resp.sample_all().await, (8,));
+    });
+    e.run();
+}
+
+#[test]
+fn sample_concurrently() {
+    let state = EngineState::new_shareable();
+    let (x, t): (Pair<u8, 2>, (WireId<u8>, WireId<u8>)) = { 
+        let mut s = state.lock().unwrap();
+        let x = Pair { a: [s.wires.alloc(), s.wires.alloc()], b: s.wires.alloc() };
+        (x, (s.wires.alloc(), s.wires.alloc()))
+    };
+    let mut e = Engine::new(state.clone());
+
+    // A stalled task is blocked on every wire in the bundle at once
+    e.schedule("pair", async { x.sample_all().await; });
+    e.schedule("tuple", async { t.sample_all().await; });
+    let Err(EngineErr::Stall { blocked, .. }) = e.try_run() else {
+        panic!("expected a stall");
+    };
+    assert_eq!(blocked, [
+        ("pair".to_string(), vec!["wire1".into(), "wire2".into(), "wire3".into()]),
+        ("tuple".to_string(), vec!["wire4".into(), "wire5".into()]),
+    ]);
+}
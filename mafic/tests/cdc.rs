@@ -0,0 +1,179 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{ Arc, Mutex };
+use mafic::*;
+use mafic::cdc::*;
+
+/// Returns the current tick
+fn tick(state: &Arc<Mutex<EngineState>>) -> usize {
+    state.lock().unwrap().cycle
+}
+
+#[test]
+fn synchronizer_latency() {
+    let state = EngineState::new_shareable();
+    let sync: Synchronizer<bool> = { 
+        let mut s = state.lock().unwrap();
+        let dst = s.add_domain("dst", 2, 0);
+        Synchronizer::new(&mut s, dst)
+    };
+    let mut e = Engine::new(state.clone());
+    let mut first = None;
+    for t in 0..12 { 
+        e.schedule_module(&sync);
+        e.schedule("tb", async move { sync.input.drive(t >= 3).await; });
+        e.run();
+        let out = state.lock().unwrap().wires.peek_wire(sync.output).unwrap();
+        if out && first.is_none() { 
+            first = Some(t);
+        }
+        e.step();
+    }
+    // Captured on the edges at the end of ticks 4 and 6
+    assert_eq!(first, Some(7));
+}
+
+#[test]
+fn pulse_sync() {
+    let state = EngineState::new_shareable();
+    let (sync, dst) = { 
+        let mut s = state.lock().unwrap();
+        let dst = s.add_domain("dst", 2, 1);
+        (PulseSync::new(&mut s, DomainId::DEFAULT, dst), dst)
+    };
+    let mut e = Engine::new(state.clone());
+    let pulses = Rc::new(RefCell::new(0));
+    for t in 0..20 { 
+        e.schedule_module(&sync);
+        e.schedule("tb", async move { sync.input.drive(t == 0 || t == 8).await; });
+        let p = pulses.clone();
+        e.schedule("count", async move { 
+            if sync.output.sample().await && dst.is_edge().await { 
+                *p.borrow_mut() += 1;
+            }
+        });
+        e.step();
+    }
+    assert_eq!(*pulses.borrow(), 2);
+}
+
+/// Sends values through a crossing and returns the tick in which each 
+/// value was received. 
+fn transfer<M: ModuleLike>(buf: &M, enq: Decoupled<u32>, deq: Decoupled<u32>, 
+    state: Arc<Mutex<EngineState>>, n: u32, ticks: usize) -> Vec<(usize, u32)> 
+{
+    let mut e = Engine::new(state.clone());
+    let checker = Rc::new(RefCell::new(DecoupledChecker::new("enq", enq)));
+    e.add_checker(checker.clone());
+    e.schedule("producer", async move { 
+        for x in 0..n { enq.producer().send(x).await; }
+        loop { enq.producer().idle().await; }
+    });
+    let received = Rc::new(RefCell::new(Vec::new()));
+    let rx = received.clone();
+    let st = state.clone();
+    e.schedule("consumer", async move { 
+        loop { 
+            let x = deq.consumer().recv().await;
+            rx.borrow_mut().push((tick(&st) - 1, x));
+        }
+    });
+    for _ in 0..ticks { 
+        e.schedule_module(buf);
+        e.step();
+    }
+    assert!(checker.borrow().violations.is_empty());
+    received.borrow().clone()
+}
+
+#[test]
+fn async_fifo() {
+    let state = EngineState::new_shareable();
+    let fifo: AsyncFifo<u32, 4> = { 
+        let mut s = state.lock().unwrap();
+        let wclk = s.add_domain("wclk", 2, 0);
+        let rclk = s.add_domain("rclk", 3, 1);
+        AsyncFifo::new(&mut s, wclk, rclk)
+    };
+    let res = transfer(&fifo, fifo.enq, fifo.deq, state.clone(), 8, 64);
+    assert_eq!(res.iter().map(|(_, x)| *x).collect::<Vec<_>>(), 
+        (0..8).collect::<Vec<_>>());
+    // Values are only removed on read clock edges
+    assert!(res.iter().all(|(t, _)| t % 3 == 1));
+    // The write pointer takes two read clock edges to cross
+    assert!(res[0].0 >= 4);
+}
+
+#[test]
+fn handshake_sync() {
+    let state = EngineState::new_shareable();
+    let sync: HandshakeSync<u32> = { 
+        let mut s = state.lock().unwrap();
+        let src = s.add_domain("src", 2, 0);
+        let dst = s.add_domain("dst", 3, 0);
+        HandshakeSync::new(&mut s, src, dst)
+    };
+    let res = transfer(&sync, sync.enq, sync.deq, state.clone(), 3, 64);
+    assert_eq!(res.iter().map(|(_, x)| *x).collect::<Vec<_>>(), [0, 1, 2]);
+    assert!(res.iter().all(|(t, _)| t % 3 == 0));
+}
+
+/// A counter in one clock domain
+pub struct Counter { 
+    count: RegisterId<u8>,
+    out: WireId<u8>,
+}
+impl ModuleLike for Counter { 
+    fn new_instance(state: &mut EngineState) -> Self { 
+        Self { 
+            count: state.registers.alloc_named("count", 0),
+            out: state.wires.alloc_named("count_out"),
+        }
+    }
+    async fn run(&self) { 
+        let count = self.count.sample().await;
+        self.count.drive(count.wrapping_add(1)).await;
+        self.out.drive(count).await;
+    }
+}
+
+#[test]
+fn cdc_checker() {
+    let state = EngineState::new_shareable();
+    let (ctr, sync, a, b) = { 
+        let mut s = state.lock().unwrap();
+        let a = s.add_domain("a", 2, 0);
+        let b = s.add_domain("b", 3, 0);
+        let ctr = Counter::new_instance(&mut s);
+        s.registers.set_domain(ctr.count, a);
+        let sync: Synchronizer<u8> = Synchronizer::new(&mut s, b);
+        (ctr, sync, a, b)
+    };
+    let mut e = Engine::new(state.clone());
+    let cdc = Rc::new(RefCell::new(CdcChecker::new()));
+    e.add_checker(cdc.clone());
+
+    // Through a synchronizer
+    for _ in 0..4 { 
+        e.schedule_module_in(a, &ctr);
+        e.schedule_module(&sync);
+        e.schedule_in(a, "a", async { sync.input.drive(ctr.out.sample().await).await; });
+        e.schedule_in(b, "b", async { sync.output.sample().await; });
+        e.step();
+    }
+    assert!(cdc.borrow().violations.is_empty());
+
+    // Directly sampling the register and the wire driven in domain 'a'
+    e.schedule_module_in(a, &ctr);
+    e.schedule_in(b, "b", async { 
+        ctr.count.sample().await;
+        ctr.out.sample().await;
+    });
+    e.step();
+    let signals: Vec<_> = cdc.borrow().violations.iter()
+        .map(|v| (v.signal.clone(), v.from, v.to)).collect();
+    assert_eq!(signals, [
+        ("count".to_string(), a, b), 
+        ("count_out".to_string(), a, b),
+    ]);
+}
@@ -0,0 +1,86 @@
+use mafic::*;
+
+pub struct Adder { 
+    x: WireId<u32>,
+    y: WireId<u32>,
+    z: WireId<u32>,
+}
+impl ModuleLike for Adder { 
+    fn new_instance(state: &mut EngineState) -> Self { 
+        Self { 
+            x: state.wires.alloc(),
+            y: state.wires.alloc(),
+            z: state.wires.alloc(),
+        }
+    }
+    async fn run(&self) {
+        let (x, y) = mafic::join!(self.x.sample(), self.y.sample());
+        self.z.drive(x + y).await;
+    }
+}
+
+/// Forwards the value from whichever input port is valid
+pub struct Arbiter { 
+    a: WireId<u32>,
+    b: WireId<u32>,
+    out: WireId<(char, u32)>,
+}
+impl ModuleLike for Arbiter { 
+    fn new_instance(state: &mut EngineState) -> Self { 
+        Self { 
+            a: state.wires.alloc_named("a"),
+            b: state.wires.alloc_named("b"),
+            out: state.wires.alloc_named("out"),
+        }
+    }
+    async fn run(&self) {
+        mafic::select! {
+            a = self.a.sample() => self.out.drive(('a', a)).await,
+            b = self.b.sample() => self.out.drive(('b', b)).await,
+        }
+    }
+}
+
+#[test]
+fn join_wires() {
+    let state = EngineState::new_shareable();
+    let adder = Adder::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+
+    e.schedule_module(&adder);
+    e.schedule("poke", async { 
+        adder.y.drive(2).await;
+        adder.x.drive(1).await;
+    });
+    e.schedule("peek", async { 
+        let res = sample_all((adder.x, adder.y, adder.z)).await;
+        assert_eq!(res, (1, 2, 3));
+    });
+    e.step();
+}
+
+#[test]
+fn select_wires() {
+    let state = EngineState::new_shareable();
+    let arb = Arbiter::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+
+    e.schedule_module(&arb);
+    e.schedule("poke", async { arb.b.drive(5).await; });
+    e.run();
+    assert_eq!(state.lock().unwrap().wires.peek_wire(arb.out), Some(('b', 5)));
+    e.step();
+
+    // Nothing drives either input
+    e.schedule_module(&arb);
+    let err = e.try_run().unwrap_err();
+    match err { 
+        EngineErr::Stall { cycle, blocked } => { 
+            assert_eq!(cycle, 1);
+            assert_eq!(blocked, [("Arbiter[0]".to_string(), 
+                vec!["a".to_string(), "b".to_string()])]
+            );
+        },
+        err => panic!("unexpected error: {}", err),
+    }
+}
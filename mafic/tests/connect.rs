@@ -0,0 +1,97 @@
+use mafic::*;
+
+pub struct Adder { 
+    x: WireId<u32>,
+    y: WireId<u32>,
+    z: WireId<u32>,
+}
+impl ModuleLike for Adder { 
+    fn new_instance(state: &mut EngineState) -> Self { 
+        Self { 
+            x: state.wires.alloc_named("adder.x"),
+            y: state.wires.alloc_named("adder.y"),
+            z: state.wires.alloc_named("adder.z"),
+        }
+    }
+    async fn run(&self) {
+        let x = self.x.sample().await;
+        let y = self.y.sample().await;
+        self.z.drive(x + y).await;
+    }
+}
+
+/// Connects its ports to the adder during elaboration
+pub struct Top { 
+    x: WireId<u32>,
+    y: WireId<u32>,
+    z: WireId<u32>,
+    adder: Adder,
+}
+impl ModuleLike for Top { 
+    fn new_instance(state: &mut EngineState) -> Self { 
+        let res = Self { 
+            x: state.wires.alloc(),
+            y: state.wires.alloc(),
+            z: state.wires.alloc(),
+            adder: Adder::new_instance(state),
+        };
+        state.wires.connect(res.x, res.adder.x);
+        state.wires.connect(res.adder.y, res.y);
+        state.wires.connect(res.z, res.adder.z);
+        res
+    }
+    async fn run(&self) {
+        self.adder.run().await;
+    }
+}
+
+#[test]
+fn static_nets() {
+    let state = EngineState::new_shareable();
+    let top = Top::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+
+    for _ in 0..2 {
+        e.schedule("poke", async {
+            top.x.drive(0x1111_1111).await;
+            top.y.drive(0x2222_2222).await;
+        });
+        e.schedule_module(&top.adder);
+        e.run();
+        assert_eq!(state.lock().unwrap().wires.peek_wire(top.z), 
+            Some(0x3333_3333));
+        e.step();
+    }
+}
+
+#[test]
+fn union_find() {
+    let state = EngineState::new_shareable();
+    let mut s = state.lock().unwrap();
+    let w: Vec<WireId<u8>> = (0..5).map(|_| s.wires.alloc()).collect();
+    s.wires.connect(w[0], w[1]);
+    s.wires.connect(w[2], w[3]);
+    s.wires.connect(w[3], w[1]);
+    s.wires.connect(w[0], w[2]);
+    let root = s.wires.root(w[0].id());
+    for x in &w[..4] {
+        assert_eq!(s.wires.root(x.id()), root);
+    }
+    assert_ne!(s.wires.root(w[4].id()), root);
+    assert_eq!(s.wires.net(w[2].id()).len(), 4);
+
+    s.write_wire(w[3], 7);
+    assert_eq!(s.wires.peek_wire(w[0]), Some(7));
+    assert_eq!(s.wires.peek_wire(w[4]), None);
+}
+
+#[test]
+#[should_panic(expected = "driver-to-driver")]
+fn net_with_two_drivers() {
+    let state = EngineState::new_shareable();
+    let mut s = state.lock().unwrap();
+    let (a, b) = (s.wires.alloc(), s.wires.alloc());
+    s.wires.connect(a, b);
+    s.write_wire(a, 1u8);
+    s.write_wire(b, 2u8);
+}
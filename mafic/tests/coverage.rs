@@ -0,0 +1,86 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use mafic::*;
+use mafic::coverage::GroupReport;
+
+fn run_fifo(items: &[u32], stalls: &[bool]) -> CoverageReport {
+    let state = EngineState::new_shareable();
+    let fifo: Fifo<u32, 2> = Fifo::new_instance(&mut state.lock().unwrap());
+    let enq = Driver::new(fifo.enq);
+    let ready = Driver::new(Signals::new(fifo.deq.ready).with_idle(true));
+
+    let mut cov = Coverage::new();
+    let count = cov.add(Coverpoint::wire("count", fifo.count)
+        .value("empty", 0)
+        .value("partial", 1)
+        .value("full", 2));
+    let data = cov.add(Coverpoint::wire("enq.data", fifo.enq.data)
+        .iff(fifo.enq.valid)
+        .range("low", 0..=9)
+        .range("high", 10..=u32::MAX));
+    cov.cross("count_x_data", &[count, data]);
+    let cov = Rc::new(RefCell::new(cov));
+
+    let mut tb = Testbench::new(state.clone());
+    tb.add_module(&fifo);
+    tb.add_driver("enq", &enq);
+    tb.add_driver("ready", &ready);
+    tb.engine().add_checker(cov.clone());
+    enq.extend(items.iter().copied());
+    ready.extend(stalls.iter().copied());
+    tb.run_cycles(10);
+    cov.borrow().report()
+}
+
+fn bins(group: &GroupReport) -> Vec<(&str, usize)> {
+    group.bins.iter().map(|(n, h)| (n.as_str(), *h)).collect()
+}
+
+#[test]
+fn fifo_coverage() {
+    let report = run_fifo(&[1, 2, 3], &[]);
+    assert_eq!(bins(report.group("count").unwrap()), [
+        ("empty", 7), ("partial", 3), ("full", 0),
+    ]);
+    assert_eq!(bins(report.group("enq.data").unwrap()), [("low", 3), ("high", 0)]);
+    assert_eq!(bins(report.group("count_x_data").unwrap()), [
+        ("empty/low", 1), ("empty/high", 0),
+        ("partial/low", 2), ("partial/high", 0),
+        ("full/low", 0), ("full/high", 0),
+    ]);
+    let text = report.to_string();
+    assert!(text.contains("coverpoint count (2/3 bins, 66.7%)"), "{}", text);
+    assert!(text.contains("    full: 0 (missed)"), "{}", text);
+}
+
+#[test]
+fn merge_runs() {
+    let a = run_fifo(&[1, 2, 3], &[]);
+    // Values are sampled on every cycle where enq.valid is held high
+    let b = run_fifo(&[10, 11, 12, 13], &[false, false, false]);
+    assert_eq!(b.group("count").unwrap().bins[2], ("full".to_string(), 2));
+
+    let json = a.to_json();
+    let mut merged = CoverageReport::from_json(&json).unwrap();
+    assert_eq!(merged, a);
+    merged.merge(&b);
+    assert_eq!(merged.group("count").unwrap().num_hit(), 3);
+    assert_eq!(merged.group("enq.data").unwrap().bins, [
+        ("low".to_string(), 3), ("high".to_string(), 6),
+    ]);
+    assert!(merged.percent() > a.percent());
+}
+
+#[test]
+fn json_escapes() {
+    let report = CoverageReport {
+        coverpoints: vec![GroupReport {
+            name: "op \"add\"\n".to_string(),
+            bins: vec![("a\\b".to_string(), 1)],
+        }],
+        crosses: vec![],
+    };
+    assert_eq!(CoverageReport::from_json(&report.to_json()).unwrap(), report);
+    assert!(CoverageReport::from_json("{\"coverpoints\":[}").is_err());
+}
@@ -0,0 +1,132 @@
+use std::io::{ BufRead, BufReader, Write };
+use std::net::{ TcpListener, TcpStream };
+use mafic::*;
+
+pub struct Counter {
+    count: RegisterId<u32>,
+    next: WireId<u32>,
+}
+impl ModuleLike for Counter {
+    fn new_instance(state: &mut EngineState) -> Self {
+        Self {
+            count: state.registers.alloc_named("top.count", 0),
+            next: state.wires.alloc_named("top.next"),
+        }
+    }
+    async fn run(&self) {
+        let count = self.count.sample().await;
+        self.next.drive(count + 1).await;
+        self.count.drive(self.next.sample().await).await;
+    }
+}
+
+fn session(e: &mut Engine, commands: &str) -> String {
+    let mut out = Vec::new();
+    Debugger::new(e).repl(commands.as_bytes(), &mut out).unwrap();
+    String::from_utf8(out).unwrap().replace("(mafic) ", "")
+}
+
+#[test]
+fn debugger_step_print_force() {
+    let state = EngineState::new_shareable();
+    let c = Counter::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+    e.add_module(&c);
+
+    let out = session(&mut e, "\
+        print top.count\n\
+        step 3\n\
+        print top\n\
+        force top.count 0x10\n\
+        step\n\
+        print top.count\n\
+        release top.count\n\
+        release top.count\n\
+        force top.next 1\n\
+        step\n\
+        print top\n\
+        list\n\
+        print top.missing\n\
+        tasks\n\
+        quit\n\
+        step\n");
+    assert_eq!(out, "\
+        top.count = 0\n\
+        cycle 3\n\
+        top.next = 3\n\
+        top.count = 3\n\
+        top.count = 16\n\
+        cycle 4\n\
+        top.count = 16\n\
+        released top.count\n\
+        error: 'top.count' is not forced\n\
+        top.next = 1\n\
+        cycle 5\n\
+        top.next = 1\n\
+        top.count = 1\n\
+        wire top.next\n\
+        register top.count\n\
+        error: unknown signal 'top.missing'\n\
+        ready Counter[0]\n");
+    assert_eq!(e.cycles(), 5);
+}
+
+#[test]
+fn debugger_breakpoints() {
+    let state = EngineState::new_shareable();
+    let c = Counter::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+    e.add_module(&c);
+
+    let out = session(&mut e, "\
+        break top.count == 5\n\
+        break top.next == 8\n\
+        continue\n\
+        delete 1\n\
+        continue\n\
+        continue 2\n\
+        delete 1\n");
+    assert_eq!(out, "\
+        breakpoint 1: top.count == 5\n\
+        breakpoint 2: top.next == 8\n\
+        breakpoint 1 in cycle 5: top.count == 5\n\
+        deleted breakpoint 1: top.count == 5\n\
+        breakpoint 2 in cycle 8: top.next == 8\n\
+        no breakpoint after 2 cycles (cycle 10)\n\
+        error: no breakpoint 1\n");
+}
+
+#[test]
+fn debugger_stall() {
+    let state = EngineState::new_shareable();
+    let w = state.lock().unwrap().wires.alloc_named::<u8>("w");
+    let mut e = Engine::new(state.clone());
+    e.schedule("reader", async move { w.sample().await; });
+
+    let out = session(&mut e, "step\ntasks\n");
+    assert_eq!(out, "\
+        error: stall in cycle 0: 'reader' blocked on [w];\n\
+        blocked reader on [w]\n");
+}
+
+#[test]
+fn debugger_tcp() {
+    let state = EngineState::new_shareable();
+    let c = Counter::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+    e.add_module(&c);
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let client = std::thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"step 2\nprint top.count\nquit\n").unwrap();
+        let mut lines = Vec::new();
+        for line in BufReader::new(stream).lines() {
+            lines.push(line.unwrap().replace("(mafic) ", ""));
+        }
+        lines
+    });
+    Debugger::new(&mut e).serve(&listener).unwrap();
+    assert_eq!(client.join().unwrap(), vec!["cycle 2", "top.count = 2", ""]);
+}
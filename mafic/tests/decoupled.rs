@@ -0,0 +1,121 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use mafic::*;
+use mafic::decoupled::*;
+
+#[test]
+fn send_recv() {
+    let state = EngineState::new_shareable();
+    let ch: Decoupled<u32> = Decoupled::new_named(&mut state.lock().unwrap().wires, "ch");
+    let mut e = Engine::new(state.clone());
+    let checker = Rc::new(RefCell::new(DecoupledChecker::new("ch", ch)));
+    e.add_checker(checker.clone());
+
+    let received = Rc::new(RefCell::new(Vec::new()));
+    e.schedule("producer", async { 
+        for x in 0..4 { 
+            ch.producer().send(x).await;
+        }
+        loop { ch.producer().idle().await; }
+    });
+    let rx = received.clone();
+    e.schedule("consumer", async move { 
+        // Apply backpressure every other cycle
+        loop { 
+            let x = ch.consumer().recv().await;
+            rx.borrow_mut().push(x);
+            ch.consumer().idle().await;
+        }
+    });
+    for _ in 0..10 { 
+        e.step();
+    }
+    assert_eq!(*received.borrow(), [0, 1, 2, 3]);
+    assert!(checker.borrow().violations.is_empty());
+    assert_eq!(e.task_names().count(), 2);
+}
+
+/// Sends values through a buffer and returns them in the order received
+fn through_buffer<M: ModuleLike>(buf: &M, enq: Decoupled<u32>, 
+    deq: Decoupled<u32>, state: std::sync::Arc<std::sync::Mutex<EngineState>>) 
+    -> Vec<(usize, u32)>
+{
+    let mut e = Engine::new(state.clone());
+    let checker = Rc::new(RefCell::new(DecoupledChecker::new("deq", deq)));
+    e.add_checker(checker.clone());
+
+    let received = Rc::new(RefCell::new(Vec::new()));
+    e.schedule("producer", async { 
+        for x in 0..6 { enq.producer().send(x).await; }
+        loop { enq.producer().idle().await; }
+    });
+    let rx = received.clone();
+    let st = state.clone();
+    e.schedule("consumer", async move { 
+        loop { 
+            let cycle = st.lock().unwrap().cycle;
+            if cycle % 3 == 2 { 
+                deq.consumer().idle().await;
+            } else { 
+                let x = deq.consumer().recv().await;
+                let cycle = st.lock().unwrap().cycle - 1;
+                rx.borrow_mut().push((cycle, x));
+            }
+        }
+    });
+    for _ in 0..16 { 
+        e.schedule_module(buf);
+        e.step();
+    }
+    assert!(checker.borrow().violations.is_empty());
+    received.borrow().clone()
+}
+
+#[test]
+fn pipeline_reg() {
+    let state = EngineState::new_shareable();
+    let buf: PipelineReg<u32> = PipelineReg::new_instance(&mut state.lock().unwrap());
+    let res = through_buffer(&buf, buf.enq, buf.deq, state.clone());
+    assert_eq!(res.iter().map(|(_, x)| *x).collect::<Vec<_>>(), [0, 1, 2, 3, 4, 5]);
+    // One cycle of latency
+    assert_eq!(res[0].0, 1);
+}
+
+#[test]
+fn skid_buffer() {
+    let state = EngineState::new_shareable();
+    let buf: SkidBuffer<u32> = SkidBuffer::new_instance(&mut state.lock().unwrap());
+    let res = through_buffer(&buf, buf.enq, buf.deq, state.clone());
+    assert_eq!(res.iter().map(|(_, x)| *x).collect::<Vec<_>>(), [0, 1, 2, 3, 4, 5]);
+    assert_eq!(res[0].0, 1);
+}
+
+#[test]
+fn protocol_violation() {
+    let state = EngineState::new_shareable();
+    let ch: Decoupled<u8> = Decoupled::new(&mut state.lock().unwrap().wires);
+    let mut e = Engine::new(state.clone());
+    let checker = Rc::new(RefCell::new(DecoupledChecker::new("ch", ch)));
+    e.add_checker(checker.clone());
+
+    // The producer changes the data and then drops valid while stalled
+    e.schedule("producer", async { 
+        for (valid, data) in [(true, 1), (true, 2), (false, 0)] { 
+            ch.valid.drive(valid).await;
+            ch.data.drive(data).await;
+            next_cycle().await;
+        }
+    });
+    e.schedule("consumer", async { 
+        loop { ch.consumer().idle().await; }
+    });
+    for _ in 0..3 { 
+        e.step();
+    }
+    let errors: Vec<_> = checker.borrow().violations.iter()
+        .map(|v| (v.cycle, v.error.clone())).collect();
+    assert_eq!(errors, [
+        (1, ProtocolError::DataChanged { from: "1".to_string(), to: "2".to_string() }),
+        (2, ProtocolError::ValidDropped),
+    ]);
+}
@@ -0,0 +1,93 @@
+use mafic::*;
+use mafic::event::EventLog;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Pulses `tick` once every `period` cycles.
+async fn baud_generator(tick: WireId<bool>, period: usize) {
+    loop {
+        tick.drive(true).await;
+        next_cycle().await;
+        for _ in 1..period {
+            tick.drive(false).await;
+            next_cycle().await;
+        }
+    }
+}
+
+#[test]
+fn delay_and_wait() {
+    let state = EngineState::new_shareable();
+    let tick: WireId<bool> = state.lock().unwrap().wires.alloc();
+    let fired = RefCell::new(Vec::new());
+
+    let polls = Rc::new(RefCell::new(EventLog::only(&[EventKind::TaskPolled])));
+    let mut e = Engine::new(state.clone());
+    e.add_observer(polls.clone());
+    e.schedule("baud", baud_generator(tick, 5));
+    e.schedule("timer", async {
+        delay(37).await;
+        let c = current_cycle().await;
+        fired.borrow_mut().push(c);
+        wait_until_cycle(100).await;
+        let c = current_cycle().await;
+        fired.borrow_mut().push(c);
+        // Already passed
+        wait_until_cycle(50).await;
+        delay(0).await;
+        let c = current_cycle().await;
+        fired.borrow_mut().push(c);
+        delay(200).await;
+        let c = current_cycle().await;
+        fired.borrow_mut().push(c);
+    });
+    e.schedule("ticks", async {
+        let mut ticks = Vec::new();
+        while ticks.len() < 4 {
+            if tick.sample().await {
+                ticks.push(current_cycle().await);
+            }
+            next_cycle().await;
+        }
+        assert_eq!(ticks, [0, 5, 10, 15]);
+    });
+    e.run_cycles(301).unwrap();
+    assert_eq!(*fired.borrow(), [37, 100, 100, 300]);
+
+    // The timer task is only polled on the cycles where it wakes up
+    let timer_polls = polls.borrow().events.iter().filter(|ev| matches!(ev,
+        EngineEvent::TaskPolled { name, .. } if name == "timer"
+    )).count();
+    assert_eq!(timer_polls, 4);
+}
+
+#[test]
+fn select_delays() {
+    let state = EngineState::new_shareable();
+    let irq: WireId<bool> = state.lock().unwrap().wires.alloc();
+    let fired = RefCell::new(Vec::new());
+
+    let mut e = Engine::new(state.clone());
+    e.schedule("irq", async move {
+        loop {
+            irq.drive(current_cycle().await >= 7).await;
+            next_cycle().await;
+        }
+    });
+    e.schedule("timer", async {
+        // The earliest timer wins
+        let res = select(delay(2), delay(5)).await;
+        let c = current_cycle().await;
+        fired.borrow_mut().push((c, matches!(res, Either::Left(_))));
+        let res = select(delay(5), delay(2)).await;
+        let c = current_cycle().await;
+        fired.borrow_mut().push((c, matches!(res, Either::Left(_))));
+
+        // Timeouts don't delay the edge
+        let res = select(irq.posedge(), delay(10)).await;
+        let c = current_cycle().await;
+        fired.borrow_mut().push((c, matches!(res, Either::Left(_))));
+    });
+    e.run_cycles(20).unwrap();
+    assert_eq!(*fired.borrow(), [(2, true), (4, false), (7, true)]);
+}
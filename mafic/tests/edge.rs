@@ -0,0 +1,128 @@
+use std::cell::RefCell;
+
+use mafic::*;
+
+#[test]
+fn wire_edges() {
+    let state = EngineState::new_shareable();
+    let irq: WireId<bool> = state.lock().unwrap().wires.alloc();
+    let level: WireId<u8> = state.lock().unwrap().wires.alloc();
+    let irq_driver = Driver::new(Signals::new(irq).with_idle(true));
+    irq_driver.extend([true, false, false, true, true, false]);
+    let level_driver = Driver::new(Signals::new(level).with_idle(3));
+    level_driver.extend([1, 1, 1, 2, 2]);
+    let seen = RefCell::new(Vec::new());
+    let levels = RefCell::new(Vec::new());
+
+    let mut tb = Testbench::new(state.clone());
+    tb.add_driver("irq", &irq_driver);
+    tb.add_driver("level", &level_driver);
+    tb.spawn("waiter", async {
+        irq.posedge().await;
+        let c = current_cycle().await;
+        seen.borrow_mut().push(("posedge", c));
+        irq.negedge().await;
+        let c = current_cycle().await;
+        seen.borrow_mut().push(("negedge", c));
+        irq.posedge().await;
+        let c = current_cycle().await;
+        seen.borrow_mut().push(("posedge", c));
+    });
+    tb.spawn("level", async {
+        loop {
+            let v = level.changed().await;
+            let c = current_cycle().await;
+            levels.borrow_mut().push((v, c));
+        }
+    });
+    tb.run_cycles(8);
+    assert_eq!(*seen.borrow(), [("posedge", 3), ("negedge", 5), ("posedge", 6)]);
+    assert_eq!(*levels.borrow(), [(2, 3), (3, 5)]);
+}
+
+pub struct Slow {
+    count: RegisterId<u32>,
+    phase: RegisterId<u32>,
+    busy: RegisterId<bool>,
+}
+impl ModuleLike for Slow {
+    fn new_instance(state: &mut EngineState) -> Self {
+        Self {
+            count: state.registers.alloc(0),
+            phase: state.registers.alloc(0),
+            busy: state.registers.alloc(false),
+        }
+    }
+    async fn run(&self) {
+        // Increment 'count' every 3 cycles
+        let phase = self.phase.sample().await;
+        self.phase.drive((phase + 1) % 3).await;
+        if phase == 2 {
+            self.count.drive(self.count.sample().await + 1).await;
+        }
+        self.busy.drive(phase != 0).await;
+    }
+}
+
+#[test]
+fn register_edges() {
+    let state = EngineState::new_shareable();
+    let slow = Slow::new_instance(&mut state.lock().unwrap());
+    let seen = RefCell::new(Vec::new());
+
+    let mut e = Engine::new(state.clone());
+    e.add_module(&slow);
+    e.schedule("waiter", async {
+        for _ in 0..2 {
+            let v = slow.count.changed().await;
+            let c = current_cycle().await;
+            seen.borrow_mut().push((v, c));
+        }
+        slow.busy.negedge().await;
+        let c = current_cycle().await;
+        seen.borrow_mut().push((100, c));
+        slow.busy.posedge().await;
+        let c = current_cycle().await;
+        seen.borrow_mut().push((101, c));
+    });
+    e.run_cycles(12).unwrap();
+    assert_eq!(*seen.borrow(), [(1, 3), (2, 6), (100, 7), (101, 8)]);
+}
+
+#[test]
+fn partly_driven_wire_edges() {
+    let state = EngineState::new_shareable();
+    let irq: WireId<bool> = state.lock().unwrap().wires.alloc();
+    let seen = RefCell::new(Vec::new());
+
+    let mut e = Engine::new(state.clone());
+    e.schedule("driver", async {
+        let values = [
+            Some(false), None, Some(true), None, None, 
+            Some(true), Some(false), None, Some(true),
+        ];
+        for v in values {
+            if let Some(v) = v {
+                irq.drive(v).await;
+            }
+            next_cycle().await;
+        }
+    });
+    e.schedule("waiter", async {
+        // Cycles where 'irq' is not driven are not edges
+        irq.posedge().await;
+        let c = current_cycle().await;
+        seen.borrow_mut().push(("posedge", c));
+        irq.negedge().await;
+        let c = current_cycle().await;
+        seen.borrow_mut().push(("negedge", c));
+        irq.posedge().await;
+        let c = current_cycle().await;
+        seen.borrow_mut().push(("posedge", c));
+        assert_eq!(irq.try_sample().await, Some(true));
+        next_cycle().await;
+        assert_eq!(irq.try_sample().await, None);
+    });
+    e.run_cycles(12).unwrap();
+    assert_eq!(*seen.borrow(), [("posedge", 2), ("negedge", 6), ("posedge", 8)]);
+}
@@ -0,0 +1,87 @@
+use mafic::*;
+use mafic::event::EventLog;
+use std::rc::Rc;
+use std::cell::RefCell;
+
+pub struct Counter {
+    out: WireId<u32>,
+    count: RegisterId<u32>,
+}
+impl ModuleLike for Counter {
+    fn new_instance(state: &mut EngineState) -> Self {
+        Self {
+            out: state.wires.alloc_named("out"),
+            count: state.registers.alloc_named("count", 0),
+        }
+    }
+    async fn run(&self) {
+        let value = self.count.sample().await;
+        self.out.drive(value).await;
+        self.count.drive(value + 1).await;
+    }
+}
+
+#[test]
+fn observe_events() {
+    let state = EngineState::new_shareable();
+    let c = Counter::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+
+    let all = Rc::new(RefCell::new(EventLog::new()));
+    let cycles = Rc::new(RefCell::new(EventLog::only(&[EventKind::CycleEnd])));
+    e.add_observer(all.clone());
+    e.add_observer(cycles.clone());
+
+    for _ in 0..2 {
+        e.schedule_module(&c);
+        e.step();
+    }
+
+    let events = &all.borrow().events;
+    assert_eq!(events[0], EngineEvent::TaskPolled {
+        cycle: 0, id: 0, name: "Counter[0]".into()
+    });
+    assert_eq!(events[1], EngineEvent::WireDriven {
+        cycle: 0, id: c.out.id(), name: Some("out".to_string()),
+        value: "0".to_string(),
+    });
+    assert_eq!(events[2], EngineEvent::TaskCompleted {
+        cycle: 0, id: 0, name: "Counter[0]".into()
+    });
+    assert_eq!(events[3], EngineEvent::RegisterCommitted {
+        cycle: 0, id: c.count.id(), name: Some("count".to_string()),
+        value: "1".to_string(),
+    });
+    assert_eq!(events[4], EngineEvent::CycleEnd { cycle: 0 });
+    assert_eq!(events.len(), 10);
+
+    assert_eq!(cycles.borrow().events, [
+        EngineEvent::CycleEnd { cycle: 0 },
+        EngineEvent::CycleEnd { cycle: 1 },
+    ]);
+}
+
+#[test]
+fn change_filters() {
+    let state = EngineState::new_shareable();
+    let c = Counter::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+
+    let log = Rc::new(RefCell::new(EventLog::only(&[EventKind::CycleEnd])));
+    e.add_observer(log.clone());
+    e.schedule_module(&c);
+    e.step();
+
+    // Wire events are recorded after the filter changes
+    log.borrow_mut().kinds = Some(vec![EventKind::WireDriven]);
+    e.schedule_module(&c);
+    e.step();
+
+    assert_eq!(log.borrow().events, [
+        EngineEvent::CycleEnd { cycle: 0 },
+        EngineEvent::WireDriven {
+            cycle: 1, id: c.out.id(), name: Some("out".to_string()),
+            value: "1".to_string(),
+        },
+    ]);
+}
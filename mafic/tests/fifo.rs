@@ -0,0 +1,126 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{ Arc, Mutex };
+use mafic::*;
+
+/// Send `n` values through a FIFO, with the consumer stalled during the 
+/// given cycles. Returns the cycle in which each value was removed. 
+fn run_fifo<const DEPTH: usize>(fifo: &Fifo<u32, DEPTH>, 
+    state: Arc<Mutex<EngineState>>, n: u32, stalled: &[usize]) 
+    -> Vec<(usize, u32)>
+{
+    let mut e = Engine::new(state.clone());
+    let checker = Rc::new(RefCell::new(DecoupledChecker::new("deq", fifo.deq)));
+    e.add_checker(checker.clone());
+
+    let (enq, deq) = (fifo.enq, fifo.deq);
+    e.schedule("producer", async move { 
+        for x in 0..n { enq.producer().send(x).await; }
+        loop { enq.producer().idle().await; }
+    });
+    let received = Rc::new(RefCell::new(Vec::new()));
+    let rx = received.clone();
+    let st = state.clone();
+    let cycles = 2 * n as usize + stalled.len() + 4;
+    let stalled = stalled.to_vec();
+    e.schedule("consumer", async move { 
+        loop { 
+            if stalled.contains(&st.lock().unwrap().cycle) { 
+                deq.consumer().idle().await;
+            } else { 
+                let x = deq.consumer().recv().await;
+                let cycle = st.lock().unwrap().cycle - 1;
+                rx.borrow_mut().push((cycle, x));
+            }
+        }
+    });
+    for _ in 0..cycles { 
+        e.schedule_module(fifo);
+        e.step();
+    }
+    assert!(checker.borrow().violations.is_empty());
+    received.borrow().clone()
+}
+
+fn values(res: &[(usize, u32)]) -> Vec<u32> {
+    res.iter().map(|(_, x)| *x).collect()
+}
+
+#[test]
+fn fifo_order() {
+    let state = EngineState::new_shareable();
+    let fifo: Fifo<u32, 4> = Fifo::new_instance(&mut state.lock().unwrap())
+        .with_histogram();
+    let res = run_fifo(&fifo, state.clone(), 8, &[0, 1, 2, 3, 4, 5, 6]);
+    assert_eq!(values(&res), (0..8).collect::<Vec<_>>());
+    assert_eq!(res[0].0, 7);
+
+    // The queue fills up while the consumer is stalled
+    let hist = fifo.histogram().unwrap();
+    assert_eq!(hist.len(), 5);
+    assert!(hist[4] >= 3);
+}
+
+#[test]
+fn fifo_flow() {
+    let state = EngineState::new_shareable();
+    let fifo: Fifo<u32, 2> = Fifo::new_instance(&mut state.lock().unwrap());
+    let res = run_fifo(&fifo, state.clone(), 4, &[]);
+    assert_eq!(res[0], (1, 0));
+
+    let state = EngineState::new_shareable();
+    let fifo: Fifo<u32, 2> = Fifo::new_instance(&mut state.lock().unwrap())
+        .with_flow(true);
+    let res = run_fifo(&fifo, state.clone(), 4, &[]);
+    assert_eq!(res, [(0, 0), (1, 1), (2, 2), (3, 3)]);
+}
+
+#[test]
+fn fifo_pipe() {
+    // Without 'pipe', a single-entry queue only accepts every other cycle
+    let state = EngineState::new_shareable();
+    let fifo: Fifo<u32, 1> = Fifo::new_instance(&mut state.lock().unwrap());
+    let res = run_fifo(&fifo, state.clone(), 4, &[]);
+    assert_eq!(res, [(1, 0), (3, 1), (5, 2), (7, 3)]);
+
+    let state = EngineState::new_shareable();
+    let fifo: Fifo<u32, 1> = Fifo::new_instance(&mut state.lock().unwrap())
+        .with_pipe(true);
+    let res = run_fifo(&fifo, state.clone(), 4, &[]);
+    assert_eq!(res, [(1, 0), (2, 1), (3, 2), (4, 3)]);
+}
+
+/// Keeps both ends of a FIFO idle
+pub struct Idle {
+    enq: Decoupled<u32>,
+    deq: Decoupled<u32>,
+}
+impl ModuleLike for Idle {
+    fn new_instance(state: &mut EngineState) -> Self {
+        Self {
+            enq: Decoupled::new(&mut state.wires),
+            deq: Decoupled::new(&mut state.wires),
+        }
+    }
+    async fn run(&self) {
+        self.enq.valid.drive(false).await;
+        self.deq.ready.drive(false).await;
+    }
+}
+
+#[test]
+fn fifo_histogram_half_cycles() {
+    let state = EngineState::new_shareable();
+    let fifo: Fifo<u32, 2> = {
+        let mut state = state.lock().unwrap();
+        // Modules run in both halves of each cycle
+        state.registers.alloc_edge(ClockEdge::Neg, 0u32);
+        Fifo::new_instance(&mut state).with_histogram()
+    };
+    let idle = Idle { enq: fifo.enq, deq: fifo.deq };
+    let mut e = Engine::new(state.clone());
+    e.add_module(&fifo);
+    e.add_module(&idle);
+    e.run_cycles(5).unwrap();
+    assert_eq!(fifo.histogram().unwrap(), [5, 0, 0]);
+}
@@ -0,0 +1,161 @@
+use std::cell::RefCell;
+use mafic::*;
+
+/// Samples a counter on the falling edge, and adds both values.
+pub struct Ddr {
+    count: RegisterId<u32>,
+    neg: RegisterId<u32>,
+    both: RegisterId<u32>,
+    sum: WireId<u32>,
+    seen: RefCell<Vec<(usize, ClockPhase, u32)>>,
+}
+impl ModuleLike for Ddr {
+    fn new_instance(state: &mut EngineState) -> Self {
+        Self {
+            count: state.registers.alloc(0),
+            neg: state.registers.alloc_edge(ClockEdge::Neg, 0),
+            both: state.registers.alloc_edge(ClockEdge::Both, 0),
+            sum: state.wires.alloc(),
+            seen: RefCell::new(Vec::new()),
+        }
+    }
+    async fn run(&self) {
+        let count = self.count.sample().await;
+        self.count.drive(count + 1).await;
+        self.neg.drive(count).await;
+        self.both.drive(self.both.sample().await + 1).await;
+        self.sum.drive(count + self.neg.sample().await).await;
+
+        let sum = self.sum.sample().await;
+        let (c, phase) = (current_cycle().await, clock_phase().await);
+        self.seen.borrow_mut().push((c, phase, sum));
+    }
+}
+
+#[test]
+fn negedge_registers() {
+    let state = EngineState::new_shareable();
+    let ddr = Ddr::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+    e.add_module(&ddr);
+    e.run_cycles(3).unwrap();
+
+    use ClockPhase::*;
+    assert_eq!(*ddr.seen.borrow(), vec![
+        (0, High, 0), (0, Low, 0),
+        (1, High, 1), (1, Low, 2),
+        (2, High, 3), (2, Low, 4),
+    ]);
+    let state = state.lock().unwrap();
+    assert_eq!(state.registers.peek_register(ddr.count), 3);
+    assert_eq!(state.registers.peek_register(ddr.neg), 2);
+    // Updated on both edges
+    assert_eq!(state.registers.peek_register(ddr.both), 6);
+    assert_eq!(state.phase, High);
+}
+
+#[test]
+fn no_half_cycles_without_negedge() {
+    let state = EngineState::new_shareable();
+    let ddr = Ddr::new_instance(&mut state.lock().unwrap());
+    {
+        let mut state = state.lock().unwrap();
+        state.registers.set_edge(ddr.neg, ClockEdge::Pos);
+        state.registers.set_edge(ddr.both, ClockEdge::Pos);
+    }
+    let mut e = Engine::new(state.clone());
+    e.add_module(&ddr);
+    e.run_cycles(2).unwrap();
+
+    use ClockPhase::*;
+    assert_eq!(*ddr.seen.borrow(), vec![(0, High, 0), (1, High, 1)]);
+    assert_eq!(state.lock().unwrap().registers.peek_register(ddr.both), 2);
+}
+
+/// Drives a rising-edge register only before the falling edge.
+pub struct FirstHalf {
+    n: RegisterId<u32>,
+    r: RegisterId<u32>,
+}
+impl ModuleLike for FirstHalf {
+    fn new_instance(state: &mut EngineState) -> Self {
+        Self {
+            n: state.registers.alloc_edge(ClockEdge::Neg, 0),
+            r: state.registers.alloc(0),
+        }
+    }
+    async fn run(&self) {
+        let n = self.n.sample().await;
+        self.n.drive(1).await;
+        if n == 0 {
+            self.r.drive(5).await;
+        }
+    }
+}
+
+#[test]
+fn redrive_posedge_registers() {
+    let state = EngineState::new_shareable();
+    let m = FirstHalf::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+    e.add_module(&m);
+    e.run_cycles(1).unwrap();
+
+    // The value driven before the falling edge is replaced by the value 
+    // driven (or not) after the falling edge
+    let state = state.lock().unwrap();
+    assert_eq!(state.registers.peek_register(m.n), 1);
+    assert_eq!(state.registers.peek_register(m.r), 0);
+}
+
+/// Captures an input on the falling edge.
+pub struct Capture {
+    i: WireId<u32>,
+    neg: RegisterId<u32>,
+}
+impl ModuleLike for Capture {
+    fn new_instance(state: &mut EngineState) -> Self {
+        Self {
+            i: state.wires.alloc_named("i"),
+            neg: state.registers.alloc_edge(ClockEdge::Neg, 0),
+        }
+    }
+    async fn run(&self) {
+        let i = self.i.sample().await;
+        self.neg.drive(i).await;
+    }
+}
+
+#[test]
+fn hold_values_from_other_tasks() {
+    let state = EngineState::new_shareable();
+    let (d, r) = {
+        let mut state = state.lock().unwrap();
+        (Capture::new_instance(&mut state), state.registers.alloc(0u32))
+    };
+    let mut e = Engine::new(state.clone());
+    e.add_module(&d);
+
+    // Tasks which only run before the falling edge keep their values for 
+    // the rest of the cycle
+    e.schedule("poke", async {
+        d.i.drive(3).await;
+        r.drive(7).await;
+    });
+    e.try_step().unwrap();
+    {
+        let state = state.lock().unwrap();
+        assert_eq!(state.registers.peek_register(d.neg), 3);
+        assert_eq!(state.registers.peek_register(r), 7);
+    }
+
+    // Persistent tasks sleeping across the falling edge
+    e.schedule("driver", async move {
+        for x in [4, 5] {
+            d.i.drive(x).await;
+            next_cycle().await;
+        }
+    });
+    e.run_cycles(2).unwrap();
+    assert_eq!(state.lock().unwrap().registers.peek_register(d.neg), 5);
+}
@@ -0,0 +1,87 @@
+use std::cell::RefCell;
+use mafic::*;
+
+/// Drives a latch from a counter, and reads it back.
+pub struct LatchedCount {
+    count: RegisterId<u32>,
+    latch: LatchId<u32>,
+    seen: RefCell<Vec<u32>>,
+}
+impl ModuleLike for LatchedCount {
+    fn new_instance(state: &mut EngineState) -> Self {
+        let (enable, input) = (state.wires.alloc(), state.wires.alloc());
+        Self {
+            count: state.registers.alloc(0),
+            latch: state.latches.alloc(enable, input, 100),
+            seen: RefCell::new(Vec::new()),
+        }
+    }
+    async fn run(&self) {
+        // Read before driving: the read waits for the latch inputs
+        let q = self.latch.sample().await;
+        self.seen.borrow_mut().push(q);
+    }
+}
+
+#[test]
+fn latch_transparent_and_hold() {
+    let state = EngineState::new_shareable();
+    let m = LatchedCount::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+    e.add_module(&m);
+    e.schedule("stimulus", async {
+        loop {
+            let count = m.count.sample().await;
+            m.count.drive(count + 1).await;
+            m.latch.set_enable(count % 3 == 1).await;
+            m.latch.drive(count * 10).await;
+            next_cycle().await;
+        }
+    });
+    e.run_cycles(6).unwrap();
+
+    // Transparent in cycles 1 and 4
+    assert_eq!(*m.seen.borrow(), vec![100, 10, 10, 10, 40, 40]);
+    assert_eq!(state.lock().unwrap().latches.peek_latch(m.latch), 40);
+
+    e.reset_registers();
+    assert_eq!(state.lock().unwrap().latches.peek_latch(m.latch), 100);
+}
+
+#[test]
+fn latch_captures_without_readers() {
+    let state = EngineState::new_shareable();
+    let latch = {
+        let mut state = state.lock().unwrap();
+        let (enable, input) = (state.wires.alloc(), state.wires.alloc());
+        state.latches.alloc_named("q", enable, input, 0u8)
+    };
+    let mut e = Engine::new(state.clone());
+    e.schedule("stimulus", async {
+        latch.set_enable(true).await;
+        latch.drive(7).await;
+    });
+    e.step();
+    assert_eq!(state.lock().unwrap().latches.peek_latch(latch), 7);
+}
+
+#[test]
+fn latch_loop_stalls() {
+    let state = EngineState::new_shareable();
+    let latch = {
+        let mut state = state.lock().unwrap();
+        let (enable, input) = (state.wires.alloc(), state.wires.alloc());
+        state.latches.alloc(enable, input, false)
+    };
+    let mut e = Engine::new(state.clone());
+    e.schedule("enable", latch.set_enable(true));
+    // The latch is transparent, so its output depends on itself
+    e.schedule("invert", async {
+        let q = latch.sample().await;
+        latch.drive(!q).await;
+    });
+    let err = e.try_step().unwrap_err();
+    let EngineErr::Stall { cycle: 0, blocked } = err else { panic!("{}", err) };
+    assert_eq!(blocked.len(), 1);
+    assert_eq!(blocked[0].0, "invert");
+}
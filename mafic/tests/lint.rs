@@ -0,0 +1,97 @@
+use mafic::*;
+
+pub struct Counter {
+    en: WireId<bool>,
+    out: WireId<u32>,
+    spare: WireId<u32>,
+    count: RegisterId<u32>,
+    idle: RegisterId<u32>,
+}
+impl ModuleLike for Counter {
+    fn new_instance(state: &mut EngineState) -> Self {
+        let res = Self {
+            en: state.wires.alloc_named("en"),
+            out: state.wires.alloc_named("out"),
+            spare: state.wires.alloc_named("spare"),
+            count: state.registers.alloc_named("count", 0),
+            idle: state.registers.alloc_named("idle", 0),
+        };
+        state.wires.alloc_named::<u32>("unused");
+        state.wires.set_optional(res.spare);
+        res
+    }
+    async fn run(&self) {
+        let en = self.en.sample().await;
+        let value = self.count.sample().await;
+        let _ = self.idle.sample().await;
+        self.out.drive(value).await;
+        if en {
+            self.count.drive(value + 1).await;
+        }
+    }
+}
+
+#[test]
+fn lint_wires() {
+    let state = EngineState::new_shareable();
+    let c = Counter::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+    e.enable_lint();
+
+    for _ in 0..3 {
+        e.schedule("poke", async { c.en.drive(true).await; });
+        e.schedule_module(&c);
+        e.step();
+    }
+
+    // The optional wire is never reported
+    let report = e.lint().unwrap().cycle_report().unwrap().clone();
+    assert_eq!(report.cycle, Some(2));
+    assert_eq!(report.undriven, vec!["unused".to_string()]);
+    assert_eq!(report.unread, vec!["out".to_string()]);
+
+    let report = e.lint_report().unwrap();
+    assert_eq!(report.cycle, None);
+    assert_eq!(report.undriven, vec!["unused".to_string()]);
+    assert_eq!(report.unread, vec!["out".to_string()]);
+    assert_eq!(report.unchanged, vec!["idle".to_string()]);
+    assert!(!report.is_clean());
+}
+
+/// Samples a wire only during the first half of each cycle.
+pub struct HalfReader {
+    x: WireId<u32>,
+    neg: RegisterId<u32>,
+}
+impl ModuleLike for HalfReader {
+    fn new_instance(state: &mut EngineState) -> Self {
+        Self {
+            x: state.wires.alloc_named("x"),
+            neg: state.registers.alloc_edge(ClockEdge::Neg, 0),
+        }
+    }
+    async fn run(&self) {
+        self.x.drive(1).await;
+        if clock_phase().await == ClockPhase::High {
+            let x = self.x.sample().await;
+            self.neg.drive(self.neg.sample().await + x).await;
+        }
+    }
+}
+
+#[test]
+fn lint_half_cycles() {
+    let state = EngineState::new_shareable();
+    let m = HalfReader::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+    e.enable_lint();
+    e.add_module(&m);
+    e.run_cycles(3).unwrap();
+
+    // Both halves are observed as a single cycle
+    let lint = e.lint().unwrap();
+    assert_eq!(lint.cycles(), 3);
+    assert_eq!(lint.cycle_report().unwrap().cycle, Some(2));
+    assert!(lint.cycle_report().unwrap().is_clean());
+    assert!(e.lint_report().unwrap().is_clean());
+}
@@ -0,0 +1,163 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use mafic::*;
+
+/// A counter whose register has no reset
+pub struct Counter { 
+    en: WireId<Logic>,
+    out: WireId<LogicVec<4>>,
+    count: RegisterId<LogicVec<4>>,
+}
+impl ModuleLike for Counter { 
+    fn new_instance(state: &mut EngineState) -> Self { 
+        Self { 
+            en: state.wires.alloc_four_state(),
+            out: state.wires.alloc_four_state(),
+            count: state.registers.alloc_four_state(LogicVec::new(0)),
+        }
+    }
+    async fn run(&self) {
+        let en = self.en.sample().await;
+        let count = self.count.sample().await;
+        let next = count + LogicVec::new(1);
+        if en.is_one() { 
+            self.count.drive(next).await;
+        }
+        self.out.drive(count).await;
+    }
+}
+
+#[test]
+fn logic_ops() {
+    assert_eq!(Logic::Zero & Logic::X, Logic::Zero);
+    assert_eq!(Logic::One | Logic::Z, Logic::One);
+    assert_eq!(Logic::One & Logic::Z, Logic::X);
+    assert_eq!(!Logic::Z, Logic::X);
+
+    let a = LogicVec::<4>::new(0b1100);
+    let b = LogicVec::<4>::x().with_bit(0, Logic::Zero);
+    assert_eq!(format!("{:?}", a & b), "4'bxx00");
+    assert_eq!(format!("{:?}", a | b), "4'b11x0");
+    assert_eq!(format!("{:?}", LogicVec::<4>::z()), "4'bzzzz");
+    assert_eq!(a + b, LogicVec::x());
+    assert_eq!(a + LogicVec::new(5), LogicVec::new(1));
+    assert_eq!(a.eq4(b), Logic::X);
+    assert_eq!(a.or_reduce(), Logic::One);
+    assert_eq!(b.and_reduce(), Logic::Zero);
+    assert_eq!(a.to_bits(), Some(Bits::new(0b1100)));
+    assert!(b.is_unknown());
+}
+
+#[test]
+fn missing_reset() {
+    let state = EngineState::new_shareable();
+    state.lock().unwrap().set_four_state(true);
+    let ctr = Counter::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+
+    let xcheck = Rc::new(RefCell::new(XChecker::new()));
+    xcheck.borrow_mut().watch_wire("out", ctr.out);
+    e.add_checker(xcheck.clone());
+
+    // The register powers up as X, which propagates to the output
+    e.schedule_module(&ctr);
+    e.schedule("en", async { ctr.en.drive(Logic::One).await; });
+    e.step();
+    assert_eq!(xcheck.borrow().violations, [XViolation { 
+        cycle: 0, name: "out".to_string(), value: "4'bxxxx".to_string()
+    }]);
+
+    // After reset, the output is known
+    e.reset_registers();
+    e.schedule_module(&ctr);
+    e.schedule("en", async { ctr.en.drive(Logic::One).await; });
+    e.step();
+    e.schedule_module(&ctr);
+    e.schedule("en", async { ctr.en.drive(Logic::One).await; });
+    e.step();
+    assert_eq!(xcheck.borrow().violations.len(), 1);
+    assert_eq!(state.lock().unwrap().registers.peek_register(ctr.count), 
+        LogicVec::new(2));
+}
+
+#[test]
+fn undriven_wire_reads_z() {
+    let state = EngineState::new_shareable();
+    state.lock().unwrap().set_four_state(true);
+    let ctr = Counter::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+    e.reset_registers();
+
+    // Nothing drives 'en', so the counter reads Z instead of stalling
+    e.schedule_module(&ctr);
+    e.try_run().unwrap();
+    assert_eq!(state.lock().unwrap().wires.peek_wire(ctr.en), 
+        Some(Logic::Z));
+    assert_eq!(state.lock().unwrap().wires.peek_wire(ctr.out), 
+        Some(LogicVec::new(0)));
+}
+
+#[test]
+fn undriven_wire_chain() {
+    let state = EngineState::new_shareable();
+    state.lock().unwrap().set_four_state(true);
+    let (w1, w2) = {
+        let mut state = state.lock().unwrap();
+        let w1: WireId<Logic> = state.wires.alloc_four_state();
+        let w2: WireId<Logic> = state.wires.alloc_four_state();
+        (w1, w2)
+    };
+    let mut e = Engine::new(state.clone());
+
+    // 'w2' is driven by a task which is waiting on 'w1', so only 'w1' 
+    // is undriven
+    let out = Rc::new(RefCell::new(None));
+    e.schedule("b", {
+        let out = out.clone();
+        async move { 
+            let v = w2.sample().await;
+            *out.borrow_mut() = Some(v);
+        }
+    });
+    e.schedule("a", async move {
+        let v = w1.sample().await;
+        w2.drive(!v).await;
+    });
+    e.try_run().unwrap();
+    assert_eq!(state.lock().unwrap().wires.peek_wire(w1), Some(Logic::Z));
+    assert_eq!(*out.borrow(), Some(Logic::X));
+}
+
+#[test]
+fn undriven_wire_chain_reversed() {
+    let state = EngineState::new_shareable();
+    state.lock().unwrap().set_four_state(true);
+    let (w1, w2) = {
+        let mut state = state.lock().unwrap();
+        let w1: WireId<Logic> = state.wires.alloc_four_state();
+        let w2: WireId<Logic> = state.wires.alloc_four_state();
+        (w1, w2)
+    };
+    let mut e = Engine::new(state.clone());
+
+    // 'w1' is resolved first, even though it is driven by a task which 
+    // is waiting on 'w2'
+    let out = Rc::new(RefCell::new(None));
+    e.schedule("b", {
+        let out = out.clone();
+        async move { 
+            let v = w1.sample().await;
+            *out.borrow_mut() = Some(v);
+        }
+    });
+    e.schedule("a", async move {
+        let v = w2.sample().await;
+        w1.drive(!v).await;
+    });
+    e.try_run().unwrap();
+
+    // The driven value replaces the undriven value
+    assert_eq!(*out.borrow(), Some(Logic::Z));
+    assert_eq!(state.lock().unwrap().wires.peek_wire(w2), Some(Logic::Z));
+    assert_eq!(state.lock().unwrap().wires.peek_wire(w1), Some(Logic::X));
+}
@@ -0,0 +1,129 @@
+use mafic::*;
+
+pub struct Inverter {
+    i: WireId<bool>,
+    o: WireId<bool>,
+}
+impl ModuleLike for Inverter {
+    fn new_instance(state: &mut EngineState) -> Self {
+        Self {
+            i: state.wires.alloc(),
+            o: state.wires.alloc(),
+        }
+    }
+    async fn run(&self) {
+        let i = self.i.sample().await;
+        self.o.drive(!i).await;
+    }
+}
+
+/// An inverter whose name is chosen by the parent module
+pub struct NamedInverter {
+    name: String,
+    inv: Inverter,
+}
+impl NamedInverter {
+    pub fn new(state: &mut EngineState, name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            inv: Inverter::new_instance(state),
+        }
+    }
+}
+impl ModuleLike for NamedInverter {
+    fn new_instance(state: &mut EngineState) -> Self {
+        Self::new(state, "inv")
+    }
+    fn name(&self) -> Option<String> {
+        Some(self.name.clone())
+    }
+    async fn run(&self) {
+        self.inv.run().await;
+    }
+}
+
+#[test]
+fn task_names() {
+    let state = EngineState::new_shareable();
+    let (a, b, c) = {
+        let mut state = state.lock().unwrap();
+        (
+            Inverter::new_instance(&mut state),
+            Inverter::new_instance(&mut state),
+            NamedInverter::new(&mut state, "top.inv"),
+        )
+    };
+    let mut e = Engine::new(state.clone());
+
+    for _ in 0..2 {
+        e.schedule_module(&b);
+        e.schedule_module(&a);
+        e.schedule_module(&c);
+        for idx in 0..2 {
+            e.schedule(format!("poke[{}]", idx), async {});
+        }
+
+        // Instance names are stable across cycles
+        let names: Vec<&str> = e.task_names().collect();
+        assert_eq!(names,
+            ["Inverter[0]", "Inverter[1]", "top.inv", "poke[0]", "poke[1]"]
+        );
+
+        e.schedule("poke", async {
+            a.i.drive(true).await;
+            b.i.drive(false).await;
+            c.inv.i.drive(false).await;
+        });
+        e.step();
+    }
+}
+
+/// A module with no wires or registers
+pub struct Idle {
+    instance: InstanceId,
+}
+impl ModuleLike for Idle {
+    fn new_instance(state: &mut EngineState) -> Self {
+        Self { instance: state.alloc_instance::<Self>() }
+    }
+    fn instance(&self) -> Option<InstanceId> { Some(self.instance) }
+    async fn run(&self) {}
+}
+
+#[test]
+fn instance_ids() {
+    let state = EngineState::new_shareable();
+    let (a, b) = {
+        let mut state = state.lock().unwrap();
+        (Idle::new_instance(&mut state), Idle::new_instance(&mut state))
+    };
+    let mut e = Engine::new(state.clone());
+
+    // Instances with an identifier are numbered in construction order, and
+    // keep their names across cycles
+    for _ in 0..2 {
+        e.schedule_module(&b);
+        e.schedule_module(&a);
+        let names: Vec<&str> = e.task_names().collect();
+        assert_eq!(names, ["Idle[1]", "Idle[0]"]);
+        e.step();
+    }
+}
+
+#[test]
+fn library_instance_ids() {
+    let state = EngineState::new_shareable();
+    let (a, b) = {
+        let mut state = state.lock().unwrap();
+        (
+            Fifo::<u8, 2>::new_instance(&mut state),
+            Fifo::<u8, 2>::new_instance(&mut state),
+        )
+    };
+    let mut e = Engine::new(state.clone());
+
+    e.schedule_module(&b);
+    e.schedule_module(&a);
+    let names: Vec<&str> = e.task_names().collect();
+    assert_eq!(names, ["Fifo[1]", "Fifo[0]"]);
+}
@@ -0,0 +1,59 @@
+use std::cell::{ Cell, RefCell };
+use std::rc::Rc;
+
+use mafic::*;
+use mafic::order::{ StateTrace, compare_orders };
+
+fn run_fifo(seed: Option<u64>) -> StateTrace {
+    let state = EngineState::new_shareable();
+    let fifo: Fifo<u32, 2> = Fifo::new_instance(&mut state.lock().unwrap());
+    let enq = Driver::new(fifo.enq);
+    let ready = Driver::new(Signals::new(fifo.deq.ready).with_idle(true));
+    let trace = Rc::new(RefCell::new(StateTrace::new()));
+
+    let mut tb = Testbench::new(state.clone());
+    tb.engine().set_task_order(seed);
+    tb.engine().add_checker(trace.clone());
+    tb.add_module(&fifo);
+    tb.add_driver("enq", &enq);
+    tb.add_driver("ready", &ready);
+    enq.extend([1, 2, 3, 4, 5]);
+    ready.extend([false, true, false, false, true]);
+    tb.run_cycles(12);
+    trace.take()
+}
+
+#[test]
+fn fifo_is_order_independent() {
+    assert_eq!(compare_orders(1..=16, run_fifo), Ok(()));
+    assert_eq!(run_fifo(None).cycles.len(), 12);
+}
+
+/// Passes a value between two tasks through shared state instead of a
+/// wire, which only works when the writer is polled first.
+fn run_shared(seed: Option<u64>) -> StateTrace {
+    let state = EngineState::new_shareable();
+    let out: WireId<usize> = state.lock().unwrap().wires.alloc_named("out");
+    let shared = Cell::new(0);
+    let trace = Rc::new(RefCell::new(StateTrace::new()));
+
+    let mut e = Engine::new(state.clone());
+    e.set_task_order(seed);
+    e.add_checker(trace.clone());
+    for cycle in 1..=8 {
+        let shared = &shared;
+        e.schedule("writer", async move { shared.set(cycle) });
+        e.schedule("reader", async move { out.drive(shared.get()).await });
+        e.step();
+    }
+    trace.take()
+}
+
+#[test]
+fn shared_state_is_order_dependent() {
+    let err = compare_orders(1..=16, run_shared).unwrap_err();
+    assert_eq!(err.signal, "out");
+    assert_eq!(err.expected, Some((err.cycle + 1).to_string()));
+    assert_eq!(err.found, Some(err.cycle.to_string()));
+    assert!(err.to_string().contains(&format!("'out' in cycle {}", err.cycle)));
+}
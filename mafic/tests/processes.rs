@@ -0,0 +1,81 @@
+use mafic::*;
+
+pub struct ReadPort { 
+    idx: WireId<usize>,
+    data: WireId<usize>,
+}
+
+pub struct WritePort { 
+    idx: WireId<usize>,
+    data: WireId<usize>,
+    ack: WireId<bool>,
+}
+
+pub struct RAM<const SZ: usize> { 
+    rp: ReadPort,
+    wp: WritePort,
+    data: [RegisterId<usize>; SZ],
+}
+impl <const SZ: usize> RAM<SZ> { 
+    async fn do_readport(&self) {
+        let idx = self.rp.idx.sample().await;
+        let val = self.data[idx].sample().await;
+        self.rp.data.drive(val).await;
+    }
+    async fn do_writeport(&self) {
+        let idx = self.wp.idx.sample().await;
+        let val = self.wp.data.sample().await;
+        self.data[idx].drive(val).await;
+        self.wp.ack.drive(true).await;
+    }
+}
+impl <const SZ: usize> ModuleLike for RAM<SZ> {
+    fn new_instance(state: &mut EngineState) -> Self { 
+        Self { 
+            rp: ReadPort { 
+                idx: state.wires.alloc(),
+                data: state.wires.alloc(),
+            },
+            wp: WritePort { 
+                idx: state.wires.alloc(),
+                data: state.wires.alloc(),
+                ack: state.wires.alloc(),
+            },
+            data: std::array::from_fn(|_| state.registers.alloc(0)),
+        }
+    }
+    async fn run(&self) {
+        join(self.do_readport(), self.do_writeport()).await;
+    }
+    fn processes(&self) -> Processes<'_> {
+        Processes::new()
+            .with("read", self.do_readport())
+            .with("write", self.do_writeport())
+    }
+}
+
+#[test]
+fn independent_processes() {
+    let state = EngineState::new_shareable();
+    let ram: RAM<4> = RAM::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+
+    // The read port inputs only become available after the write port has
+    // been acknowledged. This stalls forever if the read port and write 
+    // port are described by a single process. 
+    let testbench = async {
+        ram.wp.idx.drive(1).await;
+        ram.wp.data.drive(0xdead).await;
+        assert!(ram.wp.ack.sample().await);
+        ram.rp.idx.drive(1).await;
+    };
+    e.schedule_module(&ram);
+    e.schedule("testbench", testbench);
+
+    let names: Vec<&str> = e.task_names().collect();
+    assert_eq!(names, ["RAM[0].read", "RAM[0].write", "testbench"]);
+
+    e.step();
+    let data = state.lock().unwrap().registers.peek_register(ram.data[1]);
+    assert_eq!(data, 0xdead);
+}
@@ -0,0 +1,59 @@
+use mafic::*;
+
+#[test]
+fn reproducible() {
+    let dist = Random::range(0..=99u32);
+    let mut a = Rng::new(42);
+    let mut b = Rng::new(42);
+    let xs: Vec<u32> = (0..32).map(|_| dist.sample(&mut a)).collect();
+    let ys: Vec<u32> = (0..32).map(|_| dist.sample(&mut b)).collect();
+    assert_eq!(xs, ys);
+    assert!(xs.iter().all(|x| *x <= 99));
+    assert_ne!(xs, (0..32).map(|_| dist.sample(&mut Rng::new(43))).collect::<Vec<_>>());
+}
+
+#[test]
+fn constraints() {
+    let mut rng = Rng::new(1);
+    let even = Random::range(-8..=8i8).filter(|x| x % 2 == 0);
+    assert!((0..100).all(|_| even.sample(&mut rng) % 2 == 0));
+
+    // The value 7 should be drawn about 9 times as often as the range
+    let skewed = Random::range(0..=3u8).or_value(9, 7);
+    let sevens = (0..1000).filter(|_| skewed.sample(&mut rng) == 7).count();
+    assert!((850..950).contains(&sevens), "{}", sevens);
+
+    let full = Random::range(0..=u64::MAX);
+    full.sample(&mut rng);
+    assert!(Random::value(true).sample(&mut rng));
+}
+
+#[test]
+fn drive_random_wires() {
+    let state = EngineState::new_shareable();
+    state.lock().unwrap().set_seed(7);
+    let (a, b): (WireId<u8>, WireId<bool>) = {
+        let mut s = state.lock().unwrap();
+        (s.wires.alloc(), s.wires.alloc())
+    };
+    let dist_a = Random::range(10..=20u8);
+    let dist_b = Random::range(false..=true);
+    let target_a = Signals::new(a);
+    let target_b = Signals::new(b);
+    let monitor = Monitor::new(Signals::new((a, b)));
+    let rx = monitor.subscribe();
+
+    let mut tb = Testbench::new(state.clone());
+    tb.spawn("a", drive_random(&target_a, &dist_a));
+    tb.spawn("b", drive_random(&target_b, &dist_b));
+    tb.add_monitor("mon", &monitor);
+    tb.run_cycles(64);
+
+    let items: Vec<(u8, bool)> = rx.try_iter().map(|o| o.item).collect();
+    assert_eq!(items.len(), 64);
+    assert!(items.iter().all(|(a, _)| (10..=20).contains(a)));
+    assert!(items.iter().any(|(_, b)| *b) && items.iter().any(|(_, b)| !*b));
+    let rng = &state.lock().unwrap().rng;
+    assert_eq!(rng.seed(), 7);
+    assert!(rng.used());
+}
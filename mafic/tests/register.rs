@@ -22,7 +22,7 @@ impl ModuleLike for ModuleA {
 
 #[test]
 fn simple_register() {
-    let mut state = EngineState::new_shareable();
+    let state = EngineState::new_shareable();
     let mut e = Engine::new(state.clone());
 
     let out  = state.lock().unwrap().wires.alloc();
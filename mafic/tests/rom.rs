@@ -1,5 +1,4 @@
 use mafic::*;
-use std::sync::*;
 
 /// A read request 
 pub struct ReadPortReq { 
@@ -104,7 +103,7 @@ fn test_rom() {
     }
 
 
-    let mut state = EngineState::new_shareable();
+    let state = EngineState::new_shareable();
     let mut e = Engine::new(state.clone());
     let rom = ROMTestbench::new_instance(&mut state.lock().unwrap());
 
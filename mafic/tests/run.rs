@@ -0,0 +1,80 @@
+use mafic::*;
+
+/// Counts up to a limit, and then sets `done`.
+pub struct Counter {
+    limit: WireId<u32>,
+    count: RegisterId<u32>,
+    done: RegisterId<bool>,
+}
+impl ModuleLike for Counter {
+    fn new_instance(state: &mut EngineState) -> Self {
+        Self {
+            limit: state.wires.alloc(),
+            count: state.registers.alloc(0),
+            done: state.registers.alloc(false),
+        }
+    }
+    async fn run(&self) {
+        let count = self.count.sample().await;
+        if count == self.limit.sample().await {
+            self.done.drive(true).await;
+        } else {
+            self.count.drive(count + 1).await;
+        }
+    }
+}
+
+#[test]
+fn run_until_done() {
+    let state = EngineState::new_shareable();
+    let counter = Counter::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+    e.add_module(&counter);
+    e.schedule("limit", async {
+        loop {
+            counter.limit.drive(5).await;
+            next_cycle().await;
+        }
+    });
+
+    let done = counter.done;
+    let taken = e.run_until(|s| s.registers.peek_register(done)).unwrap();
+    assert_eq!(taken, 6);
+    assert_eq!(e.cycles(), 6);
+    // The condition already holds
+    assert_eq!(e.run_until(|s| s.registers.peek_register(done)).unwrap(), 0);
+    assert_eq!(e.run_cycles(3).unwrap(), 3);
+    assert_eq!(e.cycles(), 9);
+    assert_eq!(state.lock().unwrap().registers.peek_register(counter.count), 5);
+}
+
+#[test]
+fn run_until_timeout() {
+    let state = EngineState::new_shareable();
+    let counter = Counter::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+    e.add_module(&counter);
+    e.schedule("limit", async {
+        loop {
+            counter.limit.drive(100).await;
+            next_cycle().await;
+        }
+    });
+    e.set_cycle_budget(10);
+
+    let done = counter.done;
+    let err = e.run_until(|s| s.registers.peek_register(done)).unwrap_err();
+    assert!(matches!(err, EngineErr::Timeout { cycle: 10, budget: 10 }));
+    assert_eq!(err.to_string(),
+        "timeout in cycle 10: condition did not hold within 10 cycles");
+}
+
+#[test]
+fn run_cycles_stall() {
+    let state = EngineState::new_shareable();
+    let counter = Counter::new_instance(&mut state.lock().unwrap());
+    let mut e = Engine::new(state.clone());
+    // Nothing drives the limit
+    e.add_module(&counter);
+    assert!(matches!(e.run_cycles(4), Err(EngineErr::Stall { cycle: 0, .. })));
+}
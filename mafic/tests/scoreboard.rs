@@ -0,0 +1,95 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use mafic::*;
+use mafic::scoreboard::{ Mismatch, MismatchKind };
+use mafic::testbench::Observed;
+
+#[test]
+fn fifo_against_model() {
+    let state = EngineState::new_shareable();
+    let fifo: Fifo<u32, 2> = Fifo::new_instance(&mut state.lock().unwrap());
+
+    let enq = Driver::new(fifo.enq);
+    let ready = Driver::new(Signals::new(fifo.deq.ready).with_idle(true));
+    let input = Monitor::new(fifo.enq);
+    let output = Monitor::new(fifo.deq);
+
+    let sb = Rc::new(RefCell::new(
+        Scoreboard::new(MatchOrder::InOrder).with_timeout(4)
+    ));
+    sb.borrow_mut().expected_from_model(input.subscribe(), |x: u32| x);
+    sb.borrow_mut().observed_from(output.subscribe());
+
+    let mut tb = Testbench::new(state.clone());
+    tb.add_module(&fifo);
+    tb.add_driver("enq", &enq);
+    tb.add_driver("ready", &ready);
+    tb.add_monitor("input", &input);
+    tb.add_monitor("output", &output);
+    tb.engine().add_checker(sb.clone());
+
+    enq.extend([1, 2, 3, 4, 5]);
+    ready.extend([false, false, true, false]);
+    tb.run_cycles(12);
+
+    let report = sb.borrow_mut().finish();
+    assert!(report.is_ok(), "{}", report);
+    assert_eq!(report.matched, 5);
+}
+
+#[test]
+fn in_order_mismatch() {
+    let mut sb = Scoreboard::new(MatchOrder::InOrder);
+    sb.expect(1u8);
+    sb.expect(2u8);
+    sb.observe(Observed { cycle: 3, item: 2 });
+    sb.observe(Observed { cycle: 4, item: 1 });
+    let report = sb.finish();
+    assert_eq!(report.matched, 0);
+    assert_eq!(report.mismatches[0], Mismatch {
+        cycle: 3,
+        kind: MismatchKind::Mismatch,
+        expected: Some("1".to_string()),
+        observed: Some("2".to_string()),
+    });
+    assert!(report.to_string().contains("cycle 4: Mismatch, expected 2, observed 1"));
+}
+
+#[test]
+fn out_of_order() {
+    let mut sb = Scoreboard::new(MatchOrder::OutOfOrder);
+    sb.expect(1u8);
+    sb.expect(2u8);
+    sb.expect(3u8);
+    sb.observe(Observed { cycle: 3, item: 2 });
+    sb.observe(Observed { cycle: 4, item: 1 });
+    sb.observe(Observed { cycle: 5, item: 7 });
+    let report = sb.finish();
+    assert_eq!(report.matched, 2);
+    let kinds: Vec<MismatchKind> = report.mismatches.iter().map(|m| m.kind).collect();
+    assert_eq!(kinds, [MismatchKind::Missing, MismatchKind::Unexpected]);
+}
+
+#[test]
+fn timeout() {
+    let state = EngineState::new_shareable();
+    let sb = Rc::new(RefCell::new(
+        Scoreboard::<u8>::new(MatchOrder::InOrder).with_timeout(2)
+    ));
+    sb.borrow_mut().expect_at(1, 42);
+    let mut e = Engine::new(state.clone());
+    e.add_checker(sb.clone());
+    for _ in 0..4 {
+        e.step();
+    }
+    assert!(sb.borrow().mismatches.is_empty());
+    e.step();
+    assert_eq!(sb.borrow().mismatches, [Mismatch {
+        cycle: 1,
+        kind: MismatchKind::Timeout,
+        expected: Some("42".to_string()),
+        observed: None,
+    }]);
+    assert_eq!(sb.borrow().pending(), (0, 0));
+}
@@ -1,6 +1,5 @@
 
 use mafic::*;
-use std::sync::*;
 
 pub struct ModuleA { 
     msg_out: WireId<usize>,
@@ -47,7 +46,7 @@ impl ModuleLike for ModuleB {
 
 #[test]
 fn simple_test_wires() {
-    let mut state = EngineState::new_shareable();
+    let state = EngineState::new_shareable();
 
     let a = ModuleA::new_instance(&mut state.lock().unwrap());
     let b = ModuleB::new_instance(&mut state.lock().unwrap());
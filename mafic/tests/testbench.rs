@@ -0,0 +1,79 @@
+use mafic::*;
+use mafic::testbench::Observed;
+
+#[test]
+fn drive_and_monitor_fifo() {
+    let state = EngineState::new_shareable();
+    let fifo: Fifo<u32, 2> = Fifo::new_instance(&mut state.lock().unwrap());
+
+    let enq = Driver::new(fifo.enq);
+    // The consumer stalls on every third cycle
+    let ready = Driver::new(Signals::new(fifo.deq.ready).with_idle(true));
+    let deq = Monitor::new(fifo.deq);
+    let rx = deq.subscribe();
+
+    let mut tb = Testbench::new(state.clone());
+    tb.add_module(&fifo);
+    tb.add_driver("enq", &enq);
+    tb.add_driver("ready", &ready);
+    tb.add_monitor("deq", &deq);
+
+    enq.extend([10, 11, 12, 13]);
+    ready.extend([false, true, true, false, true, true, false]);
+    tb.run_cycles(1);
+    assert_eq!(enq.pending(), 3);
+    tb.run_cycles(9);
+    assert_eq!(enq.pending(), 0);
+
+    let observed: Vec<Observed<u32>> = rx.try_iter().collect();
+    assert_eq!(observed, [
+        Observed { cycle: 1, item: 10 },
+        Observed { cycle: 2, item: 11 },
+        Observed { cycle: 4, item: 12 },
+        Observed { cycle: 5, item: 13 },
+    ]);
+}
+
+#[test]
+fn monitor_signals() {
+    let state = EngineState::new_shareable();
+    let (a, b): (WireId<u8>, WireId<bool>) = { 
+        let mut s = state.lock().unwrap();
+        (s.wires.alloc(), s.wires.alloc())
+    };
+    let driver = Driver::new(Signals::new((a, b)).with_idle((0, false)));
+    let monitor = Monitor::new(Signals::new((a, b)));
+    let rx = monitor.subscribe();
+
+    let mut tb = Testbench::new(state.clone());
+    tb.add_driver("drv", &driver);
+    tb.add_monitor("mon", &monitor);
+    driver.extend([(1, true), (2, true)]);
+    tb.run_cycles(3);
+    let items: Vec<(u8, bool)> = rx.try_iter().map(|o| o.item).collect();
+    assert_eq!(items, [(1, true), (2, true), (0, false)]);
+}
+
+#[test]
+fn try_run() {
+    let state = EngineState::new_shareable();
+    let (a, b): (WireId<u8>, WireId<u8>) = { 
+        let mut s = state.lock().unwrap();
+        (s.wires.alloc_named("a"), s.wires.alloc_named("b"))
+    };
+    let driver = Driver::new(Signals::new(a).with_idle(0));
+
+    let mut tb = Testbench::new(state.clone());
+    tb.add_driver("drv", &driver);
+    tb.engine().set_cycle_budget(3);
+    assert!(matches!(tb.try_run_until(|_| false), 
+        Err(EngineErr::Timeout { cycle: 3, budget: 3 })));
+
+    // Nothing drives 'b'
+    tb.spawn("sink", async move { b.sample().await; });
+    let Err(EngineErr::Stall { cycle, blocked }) = tb.try_run_cycles(2) else {
+        panic!("expected a stall");
+    };
+    assert_eq!(cycle, 3);
+    assert_eq!(blocked, [("sink".to_string(), vec!["b".to_string()])]);
+}